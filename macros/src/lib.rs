@@ -0,0 +1,147 @@
+//! The `#[retry]` attribute of garcon, enabled by its `macros` feature.
+extern crate proc_macro;
+
+mod spec;
+
+use proc_macro::{Delimiter, Group, Literal, TokenStream, TokenTree};
+
+/// Retry a function returning a `Result` with a delay spec, e.g.
+/// `#[retry(policy = "exp:100ms*2<=30s", max_attempts = 5)]`. See `garcon::DelayPolicy`
+/// for the syntax of the policy; an invalid one fails to compile.
+///
+/// The body is called in a closure, so `?` and `return` end the attempt and its error is
+/// retried. It can't move the arguments of the function, as it may run more than once. On
+/// an `async fn`, the waits between attempts are awaited.
+#[proc_macro_attribute]
+pub fn retry(args: TokenStream, item: TokenStream) -> TokenStream {
+    expand(args, item)
+        .unwrap_or_else(|message| format!("compile_error!({:?});", message).parse().unwrap())
+}
+
+fn expand(args: TokenStream, item: TokenStream) -> Result<TokenStream, String> {
+    let spec = spec(args)?;
+
+    let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        _ => return Err("#[retry] only applies to functions with a body".to_string()),
+    };
+    let is_ident = |token: &TokenTree, name: &str| match token {
+        TokenTree::Ident(ident) => ident.to_string() == name,
+        _ => false,
+    };
+    let fn_index = tokens
+        .iter()
+        .position(|token| is_ident(token, "fn"))
+        .ok_or_else(|| "#[retry] only applies to functions".to_string())?;
+    let is_async = tokens[..fn_index]
+        .iter()
+        .any(|token| is_ident(token, "async"));
+
+    // The return type is between `->` and the body or the where clause.
+    let arrow = tokens.windows(2).position(|pair| match pair {
+        [TokenTree::Punct(a), TokenTree::Punct(b)] => a.as_char() == '-' && b.as_char() == '>',
+        _ => false,
+    });
+    let output: TokenStream = match arrow {
+        Some(arrow) => tokens[arrow + 2..]
+            .iter()
+            .take_while(|token| !is_ident(token, "where"))
+            .cloned()
+            .collect(),
+        None => return Err("#[retry] needs a function returning a Result".to_string()),
+    };
+
+    let mut attempt = TokenStream::new();
+    if is_async {
+        let mut block = parse("let __garcon_result: ");
+        block.extend(output);
+        block.extend(parse(" = "));
+        block.extend(Some(TokenTree::Group(body)));
+        block.extend(parse("; __garcon_result"));
+        attempt.extend(parse("|| async"));
+        attempt.extend(Some(TokenTree::Group(Group::new(Delimiter::Brace, block))));
+    } else {
+        attempt.extend(parse("|| -> "));
+        attempt.extend(output);
+        attempt.extend(Some(TokenTree::Group(body)));
+    }
+
+    let mut retry = parse("let mut __garcon_waiter = ");
+    retry.extend(Some(TokenTree::Literal(Literal::string(&spec))));
+    retry.extend(parse(
+        ".parse::<::garcon::Delay>().expect(\"invalid #[retry] policy\");",
+    ));
+    retry.extend(parse(if is_async {
+        "::garcon::retry_async"
+    } else {
+        "::garcon::retry"
+    }));
+    let mut call = parse("&mut __garcon_waiter,");
+    call.extend(attempt);
+    retry.extend(Some(TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        call,
+    ))));
+    if is_async {
+        retry.extend(parse(".await"));
+    }
+
+    tokens.push(TokenTree::Group(Group::new(Delimiter::Brace, retry)));
+    Ok(tokens.into_iter().collect())
+}
+
+/// The delay spec of the arguments, `policy = "<spec>"` and an optional
+/// `max_attempts = <n>`.
+fn spec(args: TokenStream) -> Result<String, String> {
+    let mut policy = None;
+    let mut max_attempts = None;
+
+    let tokens: Vec<TokenTree> = args.into_iter().collect();
+    for arg in tokens.split(|token| match token {
+        TokenTree::Punct(punct) => punct.as_char() == ',',
+        _ => false,
+    }) {
+        let (name, value) = match arg {
+            [] => continue,
+            [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Literal(value)]
+                if eq.as_char() == '=' =>
+            {
+                (name.to_string(), value.to_string())
+            }
+            _ => return Err("expected `policy = \"<spec>\"` or `max_attempts = <n>`".to_string()),
+        };
+        match name.as_str() {
+            "policy" => {
+                if !value.starts_with('"') || value.contains('\\') {
+                    return Err("the policy must be a plain string".to_string());
+                }
+                policy = Some(value.trim_matches('"').to_string());
+            }
+            "max_attempts" => {
+                let n: u64 = value
+                    .parse()
+                    .map_err(|_| format!("invalid max_attempts `{}`", value))?;
+                max_attempts = Some(n);
+            }
+            _ => return Err(format!("unknown #[retry] argument `{}`", name)),
+        }
+    }
+
+    let mut spec = policy.ok_or_else(|| "#[retry] needs a policy".to_string())?;
+    if let Some(max_attempts) = max_attempts {
+        if spec
+            .split(',')
+            .any(|part| part.trim().starts_with("max_attempts="))
+        {
+            return Err("max_attempts is given both in the policy and as an argument".to_string());
+        }
+        spec += &format!(",max_attempts={}", max_attempts);
+    }
+    spec::check(&spec)?;
+    Ok(spec)
+}
+
+fn parse(code: &str) -> TokenStream {
+    code.parse().unwrap()
+}
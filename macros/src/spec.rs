@@ -0,0 +1,177 @@
+//! A check of the delay specs at expansion time, so an invalid `#[retry]` policy fails to
+//! compile instead of panicking on the first call. This crate can't depend on garcon, so
+//! this mirrors the grammar of its parser (`garcon::DelayPolicy::from_str`), and gives the
+//! same errors. Keep both in sync.
+use std::str::FromStr;
+
+fn error(part: &str, reason: &str) -> String {
+    format!("invalid delay spec `{}`: {}", part, reason)
+}
+
+fn duration(part: &str, s: &str) -> Result<(), String> {
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let unit_nanos: u64 = match unit {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60_000_000_000,
+        "h" => 3_600_000_000_000,
+        "" => return Err(error(part, &format!("`{}` has no unit", s))),
+        _ => return Err(error(part, &format!("unknown unit `{}`", unit))),
+    };
+    let invalid = || error(part, &format!("invalid duration `{}`", s));
+    if value.contains('.') {
+        let value = value.parse::<f64>().map_err(|_| invalid())? * unit_nanos as f64;
+        if value > u64::MAX as f64 {
+            return Err(invalid());
+        }
+    } else {
+        let value = value.parse::<u64>().map_err(|_| invalid())?;
+        value.checked_mul(unit_nanos).ok_or_else(invalid)?;
+    }
+    Ok(())
+}
+
+fn number<T: FromStr>(part: &str, s: &str) -> Result<(), String> {
+    s.parse::<T>()
+        .map(|_| ())
+        .map_err(|_| error(part, &format!("invalid number `{}`", s)))
+}
+
+/// Split `<args><=<cap>` into the args and whether there is a cap.
+fn cap<'a>(part: &str, args: &'a str) -> Result<(&'a str, bool), String> {
+    match args.find("<=") {
+        Some(i) => {
+            duration(part, &args[i + 2..])?;
+            Ok((&args[..i], true))
+        }
+        None => Ok((args, false)),
+    }
+}
+
+/// Split `<a><separator><b>`, failing with the expected syntax.
+fn pair<'a>(
+    part: &str,
+    args: &'a str,
+    separator: char,
+    expected: &str,
+) -> Result<(&'a str, &'a str), String> {
+    let i = args
+        .find(separator)
+        .ok_or_else(|| error(part, &format!("expected {}", expected)))?;
+    Ok((&args[..i], &args[i + 1..]))
+}
+
+fn policy(part: &str, name: &str, args: &str) -> Result<(), String> {
+    let (args, cap) = cap(part, args)?;
+    let no_cap = || {
+        if cap {
+            Err(error(part, "this policy has no cap"))
+        } else {
+            Ok(())
+        }
+    };
+    match name {
+        "instant" if args.is_empty() => no_cap(),
+        "throttle" => {
+            duration(part, args)?;
+            no_cap()
+        }
+        "exp" | "exponential" => {
+            let (initial, multiplier) = pair(part, args, '*', "<initial>*<multiplier>")?;
+            duration(part, initial)?;
+            number::<f32>(part, multiplier)
+        }
+        "linear" => {
+            let (initial, increment) = pair(part, args, '+', "<initial>+<increment>")?;
+            duration(part, initial)?;
+            duration(part, increment)
+        }
+        "poly" | "polynomial" => {
+            let (initial, exponent) = pair(part, args, '^', "<initial>^<exponent>")?;
+            duration(part, initial)?;
+            number::<f32>(part, exponent)
+        }
+        "fib" | "fibonacci" | "decorrelated" => duration(part, args),
+        "fixed" => {
+            for d in args.split('|') {
+                duration(part, d)?;
+            }
+            no_cap()
+        }
+        _ => Err(error(part, &format!("unknown policy `{}`", name))),
+    }
+}
+
+/// Check a delay spec: comma separated policies, followed by options.
+pub(crate) fn check(s: &str) -> Result<(), String> {
+    let mut options = Vec::new();
+
+    for part in s.split(',').map(str::trim) {
+        let split = part.find(&[':', '='][..]);
+        let (name, args) = match split {
+            Some(i) => (&part[..i], &part[i + 1..]),
+            None => (part, ""),
+        };
+        if name.is_empty() {
+            return Err(error(part, "expected a policy or an option"));
+        }
+        if split.map(|i| &part[i..i + 1]) != Some("=") {
+            policy(part, name, args)?;
+            continue;
+        }
+
+        match name {
+            "jitter" => {
+                if args != "full" && args != "equal" {
+                    return Err(error(part, "expected `full` or `equal`"));
+                }
+            }
+            "max_attempts" => number::<u64>(part, args)?,
+            "timeout" => duration(part, args)?,
+            _ => return Err(error(part, &format!("unknown option `{}`", name))),
+        }
+        if options.contains(&name) {
+            return Err(error(part, "option given more than once"));
+        }
+        options.push(name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn checks_the_syntax_of_specs() {
+        assert_eq!(check("exp:100ms*2<=30s,max_attempts=8,jitter=full"), Ok(()));
+        assert_eq!(check("throttle:1ms, fixed:1s|2.5s, timeout=1m"), Ok(()));
+        assert_eq!(check("instant"), Ok(()));
+
+        assert_eq!(
+            check("exp:100ms*x"),
+            Err("invalid delay spec `exp:100ms*x`: invalid number `x`".to_string())
+        );
+        assert_eq!(
+            check("throttle:1ms<=1s"),
+            Err("invalid delay spec `throttle:1ms<=1s`: this policy has no cap".to_string())
+        );
+        assert_eq!(
+            check("fib:10"),
+            Err("invalid delay spec `fib:10`: `10` has no unit".to_string())
+        );
+        assert_eq!(
+            check("expo:1s*2"),
+            Err("invalid delay spec `expo:1s*2`: unknown policy `expo`".to_string())
+        );
+        assert_eq!(
+            check("throttle:1s,jitter=full,jitter=equal"),
+            Err("invalid delay spec `jitter=equal`: option given more than once".to_string())
+        );
+    }
+}
@@ -0,0 +1,1021 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::random::{Rng, RngSource};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A backoff using the "decorrelated jitter" algorithm, where each wait is a random time
+/// between the base and three times the previous wait, capped.
+/// See [https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecorrelatedJitterWaiter {
+    base: Duration,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: RngSource,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    previous: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl DecorrelatedJitterWaiter {
+    pub fn new(base: Duration, cap: Duration) -> Self {
+        Self {
+            base,
+            cap,
+            rng: RngSource::default(),
+            previous: None,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Use a generator for the random delays, see [crate::JitterWaiter::with_rng].
+    pub fn with_rng<R: 'static + Rng>(mut self, rng: R) -> Self {
+        self.rng = RngSource::new(rng);
+        self
+    }
+}
+impl Waiter for DecorrelatedJitterWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.previous.ok_or(WaiterError::NotStarted)?;
+        self.previous = Some(self.base);
+        self.attempts = 0;
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.previous = Some(self.base);
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let previous = self.previous.ok_or(WaiterError::NotStarted)?;
+        let max = previous.checked_mul(3).unwrap_or(self.cap);
+        let next = self.rng.between(self.base, max).min(self.cap);
+        self.previous = Some(next);
+        self.attempts += 1;
+        Ok(next)
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "decorrelated_jitter({:?}", self.base)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(DecorrelatedJitterWaiter);
+
+/// A backoff where each wait is the sum of the two previous ones (i.e. following the
+/// Fibonacci sequence, starting with initial twice), capped. This grows slower than an
+/// exponential backoff.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FibonacciBackoffWaiter {
+    initial: Duration,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    current: Option<(Duration, Duration)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl FibonacciBackoffWaiter {
+    pub fn new(initial: Duration, cap: Duration) -> Self {
+        Self {
+            initial,
+            cap,
+            current: None,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for FibonacciBackoffWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.current.ok_or(WaiterError::NotStarted)?;
+        self.current = Some((self.initial, self.initial));
+        self.attempts = 0;
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.current = Some((self.initial, self.initial));
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let (current, next) = self.current.ok_or(WaiterError::NotStarted)?;
+        let after = current.checked_add(next).unwrap_or(self.cap).min(self.cap);
+        self.current = Some((next.min(self.cap), after));
+        self.attempts += 1;
+        Ok(current.min(self.cap))
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.current.map(|(current, _)| current.min(self.cap))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fibonacci({:?}", self.initial)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(FibonacciBackoffWaiter);
+
+/// A backoff where each wait is longer than the previous one by a fixed increment, capped.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinearBackoffWaiter {
+    initial: Duration,
+    increment: Duration,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    next: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl LinearBackoffWaiter {
+    pub fn new(initial: Duration, increment: Duration, cap: Duration) -> Self {
+        Self {
+            initial,
+            increment,
+            cap,
+            next: None,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for LinearBackoffWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.next.ok_or(WaiterError::NotStarted)?;
+        self.next = Some(self.initial);
+        self.attempts = 0;
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.next = Some(self.initial);
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let current = self.next.ok_or(WaiterError::NotStarted)?.min(self.cap);
+        self.next = Some(current.checked_add(self.increment).unwrap_or(self.cap));
+        self.attempts += 1;
+        Ok(current)
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.next.map(|next| next.min(self.cap))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "linear({:?} +{:?}", self.initial, self.increment)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(LinearBackoffWaiter);
+
+/// A backoff where the n-th wait is initial * n^exponent, capped. An exponent of 2 gives
+/// a quadratic backoff.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolynomialBackoffWaiter {
+    initial: Duration,
+    exponent: f64,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempt: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl PolynomialBackoffWaiter {
+    pub fn new(initial: Duration, exponent: f32, cap: Duration) -> Self {
+        Self {
+            initial,
+            exponent: exponent as f64,
+            cap,
+            attempt: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn delay(&self, attempt: u64) -> Duration {
+        let secs = self.initial.as_secs_f64() * (attempt as f64).powf(self.exponent);
+        if secs.is_finite() && secs < self.cap.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.cap
+        }
+    }
+}
+impl Waiter for PolynomialBackoffWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.attempt.ok_or(WaiterError::NotStarted)?;
+        self.attempt = Some(0);
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.attempt = Some(0);
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let attempt = self.attempt.ok_or(WaiterError::NotStarted)? + 1;
+        self.attempt = Some(attempt);
+        Ok(self.delay(attempt))
+    }
+    fn attempts(&self) -> usize {
+        self.attempt.unwrap_or(0) as usize
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.attempt.map(|attempt| self.delay(attempt + 1))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "polynomial({:?} n^{}", self.initial, self.exponent)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(PolynomialBackoffWaiter);
+
+/// A backoff where the n-th wait is initial * (1 + ln(n)), capped. It keeps growing, but much
+/// slower than a polynomial backoff, e.g. for pollers.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogarithmicBackoffWaiter {
+    initial: Duration,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempt: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl LogarithmicBackoffWaiter {
+    pub fn new(initial: Duration, cap: Duration) -> Self {
+        Self {
+            initial,
+            cap,
+            attempt: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn delay(&self, attempt: u64) -> Duration {
+        let secs = self.initial.as_secs_f64() * (1.0 + (attempt as f64).ln());
+        if secs.is_finite() && secs < self.cap.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.cap
+        }
+    }
+}
+impl Waiter for LogarithmicBackoffWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.attempt.ok_or(WaiterError::NotStarted)?;
+        self.attempt = Some(0);
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.attempt = Some(0);
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let attempt = self.attempt.ok_or(WaiterError::NotStarted)? + 1;
+        self.attempt = Some(attempt);
+        Ok(self.delay(attempt))
+    }
+    fn attempts(&self) -> usize {
+        self.attempt.unwrap_or(0) as usize
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.attempt.map(|attempt| self.delay(attempt + 1))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "logarithmic({:?}", self.initial)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(LogarithmicBackoffWaiter);
+
+/// A backoff where the n-th wait is initial times the n-th harmonic number, i.e.
+/// initial * (1 + 1/2 + ... + 1/n), capped. Like [LogarithmicBackoffWaiter], the waits grow
+/// with ln(n), but each wait is longer than the previous one by initial / n.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HarmonicBackoffWaiter {
+    initial: Duration,
+    cap: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempt: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl HarmonicBackoffWaiter {
+    pub fn new(initial: Duration, cap: Duration) -> Self {
+        Self {
+            initial,
+            cap,
+            attempt: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn delay(&self, attempt: u64) -> Duration {
+        let secs = self.initial.as_secs_f64() * harmonic(attempt);
+        if secs.is_finite() && secs < self.cap.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.cap
+        }
+    }
+}
+impl Waiter for HarmonicBackoffWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.attempt.ok_or(WaiterError::NotStarted)?;
+        self.attempt = Some(0);
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.attempt = Some(0);
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let attempt = self.attempt.ok_or(WaiterError::NotStarted)? + 1;
+        self.attempt = Some(attempt);
+        Ok(self.delay(attempt))
+    }
+    fn attempts(&self) -> usize {
+        self.attempt.unwrap_or(0) as usize
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.attempt.map(|attempt| self.delay(attempt + 1))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "harmonic({:?}", self.initial)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(HarmonicBackoffWaiter);
+
+/// The n-th harmonic number, approximated past the first terms where the approximation is
+/// precise enough.
+fn harmonic(n: u64) -> f64 {
+    if n <= 64 {
+        return (1..=n).map(|k| 1.0 / k as f64).sum();
+    }
+    // ln(n) + γ + 1/2n - 1/12n², with the Euler–Mascheroni constant γ.
+    let n = n as f64;
+    n.ln() + 0.577_215_664_901_532_9 + 1.0 / (2.0 * n) - 1.0 / (12.0 * n * n)
+}
+
+/// A backoff with a custom growth function, called with the previous wait and the number of
+/// waits so far to get the next one, capped. The first wait is initial. Clones get a copy of
+/// the function, with its state at the time of the clone.
+#[derive(Clone)]
+pub struct FnBackoffWaiter<F> {
+    initial: Duration,
+    cap: Duration,
+    grow: F,
+    next: Option<Duration>,
+    attempts: usize,
+    clock: Arc<dyn Clock>,
+}
+impl<F> FnBackoffWaiter<F>
+where
+    F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+{
+    pub fn new(initial: Duration, grow: F) -> Self {
+        Self {
+            initial,
+            cap: Duration::from_secs(u64::MAX),
+            grow,
+            next: None,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// The longest wait, whatever the function returns. There is no cap by default.
+    pub fn with_cap(mut self, cap: Duration) -> Self {
+        self.cap = cap;
+        self
+    }
+}
+impl<F> Waiter for FnBackoffWaiter<F>
+where
+    F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+{
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.next.ok_or(WaiterError::NotStarted)?;
+        self.start();
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.next = Some(self.initial.min(self.cap));
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let current = self.next.ok_or(WaiterError::NotStarted)?;
+        self.attempts += 1;
+        self.next = Some((self.grow)(current, self.attempts).min(self.cap));
+        Ok(current)
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.next
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fn_backoff({:?}", self.initial)?;
+        crate::describe_cap(f, self.cap)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+impl<F> std::fmt::Display for FnBackoffWaiter<F>
+where
+    F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.describe(f)
+    }
+}
+impl<F> std::fmt::Debug for FnBackoffWaiter<F>
+where
+    F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.describe(f)
+    }
+}
+
+/// A waiter that waits for each duration of a fixed schedule in order, and times out once
+/// the schedule is exhausted.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedScheduleWaiter {
+    schedule: Vec<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl FixedScheduleWaiter {
+    pub fn new(schedule: Vec<Duration>) -> Self {
+        Self {
+            schedule,
+            index: None,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for FixedScheduleWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.index.ok_or(WaiterError::NotStarted)?;
+        self.index = Some(0);
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.index = Some(0);
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let index = self.index.ok_or(WaiterError::NotStarted)?;
+        let current = *self
+            .schedule
+            .get(index)
+            .ok_or_else(|| WaiterError::Timeout {
+                elapsed: self.schedule.iter().sum(),
+                attempts: index,
+            })?;
+        self.index = Some(index + 1);
+        Ok(current)
+    }
+    fn attempts(&self) -> usize {
+        self.index.unwrap_or(0)
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.schedule.get(self.index?).copied()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fixed_schedule({:?})", self.schedule)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(FixedScheduleWaiter);
+
+/// An adaptive backoff, using additive-increase/multiplicative-decrease like TCP congestion
+/// control. The delay is multiplied after a [AimdWaiter::report_failure], and shortened by a
+/// fixed step after a [AimdWaiter::report_success], within `min` and `max`. It starts at
+/// `min`.
+///
+/// Clones share the same delay, so a clone can be kept to report results while the waiter
+/// is used elsewhere, e.g. in a [crate::Delay].
+#[derive(Clone)]
+pub struct AimdWaiter {
+    min_as_micros: u64,
+    max_as_micros: u64,
+    step_as_micros: u64,
+    multiplier: f64,
+    current_as_micros: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+impl AimdWaiter {
+    pub fn new(min: Duration, max: Duration, step: Duration, multiplier: f32) -> Self {
+        Self {
+            min_as_micros: as_micros(min),
+            max_as_micros: as_micros(max),
+            step_as_micros: as_micros(step),
+            multiplier: multiplier as f64,
+            current_as_micros: Arc::new(AtomicU64::new(as_micros(min))),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Shorten the delay by the step.
+    pub fn report_success(&self) {
+        self.update(|current| current.saturating_sub(self.step_as_micros));
+    }
+
+    /// Multiply the delay. A zero delay grows to the step instead.
+    pub fn report_failure(&self) {
+        self.update(|current| {
+            ((current as f64 * self.multiplier) as u64)
+                .max(current.saturating_add(self.step_as_micros))
+        });
+    }
+
+    /// The delay of the next wait.
+    pub fn current(&self) -> Duration {
+        Duration::from_micros(self.current_as_micros.load(Ordering::Relaxed))
+    }
+
+    fn update<F: Fn(u64) -> u64>(&self, f: F) {
+        let _ =
+            self.current_as_micros
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                    Some(f(current).max(self.min_as_micros).min(self.max_as_micros))
+                });
+    }
+}
+impl Waiter for AimdWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Ok(self.current())
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.current())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "aimd({:?}..{:?}, -{:?} x{})",
+            Duration::from_micros(self.min_as_micros),
+            Duration::from_micros(self.max_as_micros),
+            Duration::from_micros(self.step_as_micros),
+            self.multiplier
+        )
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(AimdWaiter);
+
+/// The microseconds of a duration, saturating for durations too long for a u64.
+fn as_micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// How fast the average latencies follow the samples, for the short term and the no-load
+/// baseline.
+const GRADIENT_SHORT_ALPHA: f64 = 0.5;
+const GRADIENT_LONG_ALPHA: f64 = 0.01;
+/// How much the short term latency may exceed the baseline before the limit shrinks.
+const GRADIENT_TOLERANCE: f64 = 1.5;
+/// The headroom added to the limit at each sample, so it keeps probing for more capacity.
+const GRADIENT_QUEUE: f64 = 4.0;
+/// How much of the new limit is taken at each sample.
+const GRADIENT_SMOOTHING: f64 = 0.2;
+
+struct GradientState {
+    limit: f64,
+    short_rtt: Option<f64>,
+    long_rtt: Option<f64>,
+}
+
+/// An adaptive limit from the latency gradient, like the Gradient2 limit of Netflix
+/// concurrency-limits. Each [GradientWaiter::record] of a latency compares its short term
+/// average with the long term one, taken as the latency without load: the limit grows while
+/// they are close, and shrinks in proportion when the short term latency rises, up to half
+/// at each sample. The limit is the number of concurrent requests, see
+/// [GradientWaiter::limit], and each wait lasts the short term latency divided by it, so a
+/// single caller sends as many requests as the limit allows.
+///
+/// Clones share the same limit, so a clone can be kept to record latencies while the waiter
+/// is used elsewhere.
+#[derive(Clone)]
+pub struct GradientWaiter {
+    max_limit: f64,
+    state: Arc<std::sync::Mutex<GradientState>>,
+    clock: Arc<dyn Clock>,
+}
+impl GradientWaiter {
+    pub fn new(initial_limit: usize, max_limit: usize) -> Self {
+        let max_limit = max_limit.max(1) as f64;
+        Self {
+            max_limit,
+            state: Arc::new(std::sync::Mutex::new(GradientState {
+                limit: (initial_limit as f64).max(1.0).min(max_limit),
+                short_rtt: None,
+                long_rtt: None,
+            })),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Record the latency of a request, and update the limit.
+    pub fn record(&self, rtt: Duration) {
+        let sample = rtt.as_secs_f64();
+        let mut state = self.state.lock().unwrap();
+        let short = state.short_rtt.map_or(sample, |short| {
+            short + (sample - short) * GRADIENT_SHORT_ALPHA
+        });
+        let mut long = state
+            .long_rtt
+            .map_or(sample, |long| long + (sample - long) * GRADIENT_LONG_ALPHA);
+        // Let the baseline come down faster once the latency dropped for good.
+        if long > short * 2.0 {
+            long *= 0.95;
+        }
+        state.short_rtt = Some(short);
+        state.long_rtt = Some(long);
+        if short <= 0.0 {
+            return;
+        }
+
+        let gradient = (GRADIENT_TOLERANCE * long / short).max(0.5).min(1.0);
+        let limit = state.limit * gradient + GRADIENT_QUEUE;
+        let limit = state.limit * (1.0 - GRADIENT_SMOOTHING) + limit * GRADIENT_SMOOTHING;
+        state.limit = limit.max(1.0).min(self.max_limit);
+    }
+
+    /// The number of concurrent requests currently allowed.
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+
+    fn delay(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        state.short_rtt.map_or(Duration::from_secs(0), |short| {
+            Duration::from_secs_f64(short / state.limit.floor())
+        })
+    }
+}
+impl Waiter for GradientWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Ok(self.delay())
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.delay())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gradient(limit {} of {})", self.limit(), self.max_limit)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(GradientWaiter);
+
+/// A throttle whose interval follows the latency of the operations it paces: each wait is
+/// `factor` times a moving average of the latencies given to
+/// [LatencyThrottleWaiter::record_latency], within `min` and `max`. Before the first latency,
+/// the waits last `initial`.
+///
+/// Clones share the same average, so a clone can be kept to record latencies while the
+/// waiter is used elsewhere.
+#[derive(Clone)]
+pub struct LatencyThrottleWaiter {
+    factor: f64,
+    smoothing: f64,
+    min: Duration,
+    max: Duration,
+    initial: Duration,
+    average_as_nanos: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+impl LatencyThrottleWaiter {
+    pub fn new(factor: f32, initial: Duration) -> Self {
+        Self {
+            factor: f64::from(factor).max(0.0),
+            smoothing: 0.2,
+            min: Duration::from_secs(0),
+            max: Duration::from_secs(u64::MAX),
+            initial,
+            // Zero until the first latency, as no operation takes no time at all.
+            average_as_nanos: Arc::new(AtomicU64::new(0)),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Bound the waits, whatever the latency.
+    pub fn with_bounds(mut self, min: Duration, max: Duration) -> Self {
+        self.min = min;
+        self.max = max.max(min);
+        self
+    }
+
+    /// The weight of each new latency in the average, between 0 and 1; 0.2 by default. A
+    /// higher weight follows changes faster, but also follows outliers.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = f64::from(smoothing).max(0.0).min(1.0);
+        self
+    }
+
+    /// Record the latency of an operation.
+    pub fn record_latency(&self, latency: Duration) {
+        let sample = latency.as_nanos().min(u128::from(u64::MAX)) as f64;
+        let _ =
+            self.average_as_nanos
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+                    let next = if average == 0 {
+                        sample
+                    } else {
+                        average as f64 + (sample - average as f64) * self.smoothing
+                    };
+                    Some((next as u64).max(1))
+                });
+    }
+
+    /// The moving average of the latencies, if any was recorded.
+    pub fn average_latency(&self) -> Option<Duration> {
+        match self.average_as_nanos.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn delay(&self) -> Duration {
+        let delay = match self.average_latency() {
+            Some(average) => {
+                let secs = average.as_secs_f64() * self.factor;
+                if secs.is_finite() && secs < self.max.as_secs_f64() {
+                    Duration::from_secs_f64(secs)
+                } else {
+                    self.max
+                }
+            }
+            None => self.initial,
+        };
+        delay.max(self.min).min(self.max)
+    }
+}
+impl Waiter for LatencyThrottleWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Ok(self.delay())
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.delay())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "latency_throttle({} x latency)", self.factor)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(LatencyThrottleWaiter);
+
+/// A ramp that starts with long waits and shortens them linearly to the target over the
+/// warm-up window, then keeps waiting for the target. This slowly ramps the load onto a
+/// freshly started dependency, the inverse of a backoff.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WarmupWaiter {
+    initial: Duration,
+    target: Duration,
+    window: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    started: Option<Instant>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl WarmupWaiter {
+    pub fn new(initial: Duration, target: Duration, window: Duration) -> Self {
+        Self {
+            initial,
+            target,
+            window,
+            started: None,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn delay_at(&self, elapsed: Duration) -> Duration {
+        if elapsed >= self.window {
+            return self.target;
+        }
+        let (elapsed, window) = (elapsed.as_nanos(), self.window.as_nanos());
+        let (initial, target) = (self.initial.as_nanos(), self.target.as_nanos());
+        let nanos = if initial >= target {
+            initial - (initial - target) * elapsed / window
+        } else {
+            initial + (target - initial) * elapsed / window
+        };
+        Duration::from_nanos(nanos as u64)
+    }
+}
+impl Waiter for WarmupWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.started.ok_or(WaiterError::NotStarted)?;
+        self.start();
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.started = Some(self.clock.now());
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.next_delay().ok_or(WaiterError::NotStarted)?;
+        self.attempts += 1;
+        Ok(delay)
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn elapsed(&self) -> Duration {
+        self.started.map_or(Duration::from_secs(0), |started| {
+            self.clock.now().saturating_duration_since(started)
+        })
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.started.map(|_| self.delay_at(self.elapsed()))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        self.started = self
+            .started
+            .map(|started| started.checked_sub(state.elapsed).unwrap_or(started));
+        self.attempts = state.attempts;
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "warmup({:?} to {:?} over {:?})",
+            self.initial, self.target, self.window
+        )
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(WarmupWaiter);
@@ -0,0 +1,173 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, Clock, SystemClock};
+use crate::{Delay, Waiter};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The state of a [CircuitBreaker].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CircuitState {
+    /// Calls go through, and failures are counted.
+    Closed,
+    /// Calls fail right away, until the open duration elapsed.
+    Open,
+    /// A single trial call goes through. Its success closes the circuit, its failure opens
+    /// it again.
+    HalfOpen,
+}
+
+/// The error of a call through a [CircuitBreaker].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CircuitError<E> {
+    /// The circuit is open, the call was not made.
+    Open,
+    /// The call was made and failed.
+    Failed(E),
+}
+
+impl<E> CircuitError<E> {
+    /// The error of the call, if it was made.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            CircuitError::Open => None,
+            CircuitError::Failed(e) => Some(e),
+        }
+    }
+}
+
+struct Breaker {
+    state: CircuitState,
+    failures: u32,
+    open_until: Option<Instant>,
+    waiter: Delay,
+}
+
+/// A circuit breaker, which stops calls to a failing dependency for a while so it can
+/// recover. After `threshold` failures in a row the circuit opens, and calls fail with
+/// [CircuitError::Open] without being made. Each time the circuit opens, it stays open for
+/// the next delay of the waiter, e.g. an exponential backoff, then lets a trial call through.
+/// If the waiter gives up, the circuit stays open until [CircuitBreaker::reset].
+///
+/// Clones share the same circuit.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    inner: Arc<Mutex<Breaker>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CircuitBreaker {
+    pub fn new<W: 'static + Waiter + Clone>(threshold: u32, waiter: W) -> Self {
+        let mut waiter = Delay::from(Box::new(waiter));
+        waiter.start();
+        Self {
+            threshold,
+            inner: Arc::new(Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                failures: 0,
+                open_until: None,
+                waiter,
+            })),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Use a clock instead of the system clock, for the breaker and its waiter.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.inner.lock().unwrap().waiter.set_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.inner.lock().unwrap();
+        self.update(&mut inner);
+        inner.state
+    }
+
+    /// Whether a call can be made now. When half open, this returns true once for the trial
+    /// call, whose result must then be recorded.
+    pub fn is_call_permitted(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        self.update(&mut inner);
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                // Stay open until the trial call reports back.
+                inner.state = CircuitState::Open;
+                inner.open_until = None;
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != CircuitState::Closed {
+            let _ = inner.waiter.restart();
+        }
+        inner.state = CircuitState::Closed;
+        inner.failures = 0;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = inner.failures.saturating_add(1);
+        if inner.state != CircuitState::Closed || inner.failures >= self.threshold {
+            inner.state = CircuitState::Open;
+            inner.open_until = inner
+                .waiter
+                .advance()
+                .ok()
+                .map(|d| deadline_after(self.clock.now(), d));
+        }
+    }
+
+    /// Close the circuit and restart its waiter.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = CircuitState::Closed;
+        inner.failures = 0;
+        inner.open_until = None;
+        inner.waiter.start();
+    }
+
+    /// Make a call if the circuit allows it, and record its result.
+    pub fn call<T, E, F: FnOnce() -> Result<T, E>>(&self, op: F) -> Result<T, CircuitError<E>> {
+        self.acquire()?;
+        self.record(op())
+    }
+
+    pub(crate) fn acquire<E>(&self) -> Result<(), CircuitError<E>> {
+        if self.is_call_permitted() {
+            Ok(())
+        } else {
+            Err(CircuitError::Open)
+        }
+    }
+
+    pub(crate) fn record<T, E>(&self, result: Result<T, E>) -> Result<T, CircuitError<E>> {
+        match result {
+            Ok(value) => {
+                self.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                self.record_failure();
+                Err(CircuitError::Failed(e))
+            }
+        }
+    }
+
+    /// Move from open to half open once the open duration elapsed.
+    fn update(&self, inner: &mut Breaker) {
+        if inner.state == CircuitState::Open {
+            if let Some(open_until) = inner.open_until {
+                if self.clock.now() >= open_until {
+                    inner.state = CircuitState::HalfOpen;
+                }
+            }
+        }
+    }
+}
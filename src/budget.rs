@@ -0,0 +1,125 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The error of [crate::retry_with_budget].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BudgetError<E> {
+    /// The retry budget is empty, with the error of the last attempt.
+    Exhausted(E),
+    /// The waiter gave up, with the error of the last attempt.
+    Failed(E),
+}
+
+impl<E> BudgetError<E> {
+    pub fn into_inner(self) -> E {
+        match self {
+            BudgetError::Exhausted(e) | BudgetError::Failed(e) => e,
+        }
+    }
+}
+
+struct Budget {
+    balance: f64,
+    refilled: Instant,
+}
+
+/// A budget of retries shared by call sites, so retries stay a fraction of the requests
+/// during an outage instead of multiplying the load. Each request deposits `ratio` retries,
+/// and each retry withdraws one. The budget also refills by `min_per_second` retries every
+/// second, so rare requests can still retry, and holds at most `max_balance` retries.
+///
+/// Clones share the same budget.
+#[derive(Clone)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_per_second: f64,
+    max_balance: f64,
+    inner: Arc<Mutex<Budget>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RetryBudget {
+    /// A budget allowing `ratio` retries per request (e.g. 0.2 for 20%), 10 retries per
+    /// second whatever the traffic, and at most 100 retries in reserve.
+    pub fn new(ratio: f64) -> Self {
+        let clock = SystemClock::shared();
+        Self {
+            ratio: ratio.max(0.0),
+            min_per_second: 10.0,
+            max_balance: 100.0,
+            inner: Arc::new(Mutex::new(Budget {
+                balance: 10.0,
+                refilled: clock.now(),
+            })),
+            clock,
+        }
+    }
+
+    /// The retries allowed every second whatever the traffic. This is also the balance the
+    /// budget starts with.
+    pub fn with_min_per_second(mut self, min_per_second: f64) -> Self {
+        self.min_per_second = min_per_second.max(0.0);
+        self.inner.lock().unwrap().balance = self.min_per_second.min(self.max_balance);
+        self
+    }
+
+    /// The most retries the budget holds, so a quiet period doesn't allow a retry storm.
+    pub fn with_max_balance(mut self, max_balance: f64) -> Self {
+        self.max_balance = max_balance.max(0.0);
+        let mut inner = self.inner.lock().unwrap();
+        inner.balance = inner.balance.min(self.max_balance);
+        drop(inner);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.inner.lock().unwrap().refilled = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Record a request, depositing its share of retries.
+    pub fn deposit(&self) {
+        self.update(|balance| balance + self.ratio);
+    }
+
+    /// Withdraw a retry, or return false if the budget is empty.
+    pub fn try_withdraw(&self) -> bool {
+        let mut withdrawn = false;
+        self.update(|balance| {
+            withdrawn = balance >= 1.0;
+            if withdrawn {
+                balance - 1.0
+            } else {
+                balance
+            }
+        });
+        withdrawn
+    }
+
+    /// The number of retries left in the budget.
+    pub fn available(&self) -> usize {
+        let mut available = 0.0;
+        self.update(|balance| {
+            available = balance;
+            balance
+        });
+        available as usize
+    }
+
+    /// Give back a retry that was not made.
+    pub(crate) fn refund(&self) {
+        self.update(|balance| balance + 1.0);
+    }
+
+    fn update<F: FnOnce(f64) -> f64>(&self, f: F) {
+        let now = self.clock.now();
+        let mut inner = self.inner.lock().unwrap();
+        let refill =
+            now.saturating_duration_since(inner.refilled).as_secs_f64() * self.min_per_second;
+        inner.refilled = now;
+        inner.balance = f((inner.balance + refill).min(self.max_balance)).min(self.max_balance);
+    }
+}
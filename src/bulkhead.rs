@@ -0,0 +1,95 @@
+#![cfg(not(feature = "no_std"))]
+use crate::{Waiter, WaiterError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct BulkheadState {
+    max: usize,
+    active: AtomicUsize,
+}
+
+/// A limit on the number of concurrent operations. Callers over the limit either fail
+/// right away with [Bulkhead::try_acquire], or wait for a slot using a waiter with
+/// [Bulkhead::acquire], until the waiter gives up.
+///
+/// Clones share the same slots.
+#[derive(Clone)]
+pub struct Bulkhead {
+    state: Arc<BulkheadState>,
+}
+
+/// A slot of a [Bulkhead], released when dropped.
+pub struct BulkheadPermit {
+    state: Arc<BulkheadState>,
+}
+
+impl Drop for BulkheadPermit {
+    fn drop(&mut self) {
+        self.state.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl Bulkhead {
+    pub fn new(max: usize) -> Self {
+        Self {
+            state: Arc::new(BulkheadState {
+                max,
+                active: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// The number of free slots.
+    pub fn available(&self) -> usize {
+        self.state
+            .max
+            .saturating_sub(self.state.active.load(Ordering::Acquire))
+    }
+
+    /// Take a slot if one is free.
+    pub fn try_acquire(&self) -> Option<BulkheadPermit> {
+        let state = &self.state;
+        state
+            .active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                if active < state.max {
+                    Some(active + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| BulkheadPermit {
+                state: self.state.clone(),
+            })
+    }
+
+    /// Take a slot, waiting with the waiter while none is free. The waiter is started first.
+    pub fn acquire<W: Waiter + ?Sized>(
+        &self,
+        waiter: &mut W,
+    ) -> Result<BulkheadPermit, WaiterError> {
+        waiter.start();
+        loop {
+            if let Some(permit) = self.try_acquire() {
+                return Ok(permit);
+            }
+            waiter.wait()?;
+        }
+    }
+
+    /// Async version of [Bulkhead::acquire].
+    #[cfg(feature = "async")]
+    pub async fn acquire_async<W: Waiter + ?Sized>(
+        &self,
+        waiter: &mut W,
+    ) -> Result<BulkheadPermit, WaiterError> {
+        waiter.start();
+        loop {
+            if let Some(permit) = self.try_acquire() {
+                return Ok(permit);
+            }
+            waiter.async_wait().await?;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock, WakeHandle};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// State shared between a [CancelHandle] and its waiters.
+#[derive(Default)]
+struct CancelState {
+    cancelled: Mutex<bool>,
+    wake: WakeHandle,
+    /// The wakers of the pending async waits.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A handle to cancel waits. Cancelling wakes up all blocking and async waits of the waiters
+/// using this handle, and makes them return [WaiterError::Cancelled] from then on.
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+    state: Arc<CancelState>,
+}
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        *self.state.cancelled.lock().unwrap() = true;
+        #[cfg(feature = "async")]
+        self.state.wakers.wake_all();
+        self.state.wake.wake();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.cancelled.lock().unwrap()
+    }
+
+    fn check(&self) -> Result<(), WaiterError> {
+        if self.is_cancelled() {
+            Err(WaiterError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleep with the clock until the duration elapsed, or until cancelled.
+    fn sleep(&self, clock: &dyn Clock, duration: Duration) -> Result<(), WaiterError> {
+        if clock.sleep_interruptible(duration, &self.state.wake, &|| self.is_cancelled()) {
+            Err(WaiterError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A future that resolves when the handle is cancelled.
+#[cfg(feature = "async")]
+struct CancelledFuture {
+    handle: CancelHandle,
+    slot: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl CancelledFuture {
+    fn new(handle: CancelHandle) -> Self {
+        let slot = handle.state.wakers.slot();
+        Self { handle, slot }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for CancelledFuture {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = self.handle.state.clone();
+        let cancelled = state.cancelled.lock().unwrap();
+        if *cancelled {
+            return Poll::Ready(());
+        }
+        self.slot.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// A waiter whose waits can be interrupted with a [CancelHandle]. Blocking waits sleep with
+/// the clock of this waiter, which [Waiter::set_clock] also sets.
+#[derive(Clone)]
+pub struct CancelableWaiter<W: Waiter> {
+    inner: W,
+    handle: CancelHandle,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> CancelableWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_handle(inner, CancelHandle::new())
+    }
+
+    /// Create a waiter cancelled by an existing handle.
+    pub fn with_handle(inner: W, handle: CancelHandle) -> Self {
+        Self {
+            inner,
+            handle,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn handle(&self) -> CancelHandle {
+        self.handle.clone()
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for CancelableWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.handle.check()?;
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.handle.check()?;
+        let delay = self.inner.advance();
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::wait_span(self, &delay);
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+        self.handle.sleep(self.clock.as_ref(), delay?)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.handle.check()?;
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        f.write_str(", cancelable")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        use futures_util::future::{select, Either};
+
+        if let Err(e) = self.handle.check() {
+            return Box::pin(futures_util::future::err(e));
+        }
+        let wait = self.inner.async_wait();
+        let cancelled = CancelledFuture::new(self.handle.clone());
+        Box::pin(async move {
+            match select(wait, cancelled).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Err(WaiterError::Cancelled),
+            }
+        })
+    }
+}
+
+describe_waiter!(CancelableWaiter<W>);
+
+/// A signal to cancel many waiters at once, e.g. when the process is shutting down.
+/// Triggering it cancels the current and future waits of every registered waiter.
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    handle: CancelHandle,
+}
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap a waiter so its waits are cancelled when this signal is triggered.
+    pub fn register<W: Waiter>(&self, waiter: W) -> CancelableWaiter<W> {
+        CancelableWaiter::with_handle(waiter, self.handle.clone())
+    }
+
+    pub fn trigger(&self) {
+        self.handle.cancel()
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.handle.is_cancelled()
+    }
+
+    /// A signal triggered on Ctrl-C (SIGINT) or SIGTERM, so long waits end promptly when
+    /// the process is asked to stop. The signal handler is process-wide: this fails if it
+    /// was already installed, by this or by another crate. Clone the signal to share it.
+    #[cfg(feature = "signals")]
+    pub fn on_termination() -> Result<Self, ctrlc::Error> {
+        let signal = Self::new();
+        let trigger = signal.clone();
+        ctrlc::set_handler(move || trigger.trigger())?;
+        Ok(signal)
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::deadline_after;
+use crate::{Waiter, WaiterError};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Extension methods to wait for the delay of a waiter or for a message on a channel,
+/// whichever comes first, e.g. for control loops that back off between attempts but still
+/// take commands.
+///
+/// These wait using the system time, not the clock of the waiter, as channels only time out
+/// on the system time. If the sending side is dropped, the rest of the delay is still
+/// waited, so a loop doesn't spin on a disconnected channel.
+pub trait WaiterRecvExt: Waiter {
+    /// Wait for the next delay, or until a message arrives on the channel. Returns the
+    /// message, or `None` if the whole delay elapsed.
+    fn wait_or_recv<T>(&mut self, receiver: &mpsc::Receiver<T>) -> Result<Option<T>, WaiterError> {
+        let delay = self.advance()?;
+        let deadline = deadline_after(Instant::now(), delay);
+        match receiver.recv_timeout(delay) {
+            Ok(message) => Ok(Some(message)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                sleep_until(deadline);
+                Ok(None)
+            }
+        }
+    }
+
+    /// [WaiterRecvExt::wait_or_recv] for a crossbeam channel.
+    #[cfg(feature = "crossbeam")]
+    fn wait_or_recv_crossbeam<T>(
+        &mut self,
+        receiver: &crossbeam_channel::Receiver<T>,
+    ) -> Result<Option<T>, WaiterError> {
+        use crossbeam_channel::RecvTimeoutError;
+
+        let delay = self.advance()?;
+        let deadline = deadline_after(Instant::now(), delay);
+        match receiver.recv_timeout(delay) {
+            Ok(message) => Ok(Some(message)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                sleep_until(deadline);
+                Ok(None)
+            }
+        }
+    }
+}
+impl<W: Waiter + ?Sized> WaiterRecvExt for W {}
+
+fn sleep_until(deadline: Instant) {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining > Duration::from_secs(0) {
+        std::thread::sleep(remaining);
+    }
+}
@@ -0,0 +1,762 @@
+#![cfg(not(feature = "no_std"))]
+use crate::{Waiter, WaiterError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A source of time for waiters. Waiters never call [Instant::now] or sleep directly, but go
+/// through their clock, which can be replaced with [crate::Waiter::set_clock].
+pub trait Clock: Send + Sync {
+    /// The current time.
+    fn now(&self) -> Instant;
+
+    /// The current wall clock time, for waits until a time of day.
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// Block the current thread for a duration.
+    fn sleep(&self, duration: Duration);
+
+    /// Busy-wait for a duration, for waits that can't afford the overshoot of sleeping.
+    /// Clocks whose time doesn't pass on its own need to override this.
+    fn spin(&self, duration: Duration) {
+        let deadline = deadline_after(self.now(), duration);
+        while self.now() < deadline {
+            #[allow(deprecated)]
+            std::sync::atomic::spin_loop_hint();
+        }
+    }
+
+    /// Block like [Clock::sleep], but return early once `interrupted` returns true, which is
+    /// checked again each time the handle is woken. Returns whether it was interrupted. By
+    /// default this parks the thread for the duration of system time.
+    fn sleep_interruptible(
+        &self,
+        duration: Duration,
+        handle: &WakeHandle,
+        interrupted: &dyn Fn() -> bool,
+    ) -> bool {
+        handle.park(deadline_after(Instant::now(), duration), interrupted)
+    }
+
+    /// A future that resolves after a duration.
+    #[cfg(feature = "async")]
+    fn async_sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    /// Like [Clock::async_sleep], for clocks that can sleep without boxing the future. By
+    /// default this boxes [Clock::async_sleep].
+    #[cfg(feature = "async")]
+    fn clock_sleep(&self, duration: Duration) -> ClockSleep {
+        ClockSleep::boxed(self.async_sleep(duration))
+    }
+}
+
+/// The future of [Clock::clock_sleep].
+#[cfg(feature = "async")]
+pub struct ClockSleep(SleepKind);
+
+#[cfg(feature = "async")]
+enum SleepKind {
+    Done,
+    Boxed(Pin<Box<dyn Future<Output = ()> + Send>>),
+    #[cfg(not(any(
+        feature = "wasm",
+        feature = "tokio",
+        feature = "async-std",
+        feature = "smol"
+    )))]
+    Timer(future::ThrottleTimerFuture),
+}
+
+#[cfg(feature = "async")]
+impl ClockSleep {
+    pub fn boxed(future: Pin<Box<dyn Future<Output = ()> + Send>>) -> Self {
+        ClockSleep(SleepKind::Boxed(future))
+    }
+
+    /// A sleep that is already over.
+    pub fn done() -> Self {
+        ClockSleep(SleepKind::Done)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for ClockSleep {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.0 {
+            SleepKind::Done => Poll::Ready(()),
+            SleepKind::Boxed(future) => future.as_mut().poll(cx),
+            #[cfg(not(any(
+                feature = "wasm",
+                feature = "tokio",
+                feature = "async-std",
+                feature = "smol"
+            )))]
+            SleepKind::Timer(timer) => Pin::new(timer).poll(cx),
+        }
+    }
+}
+
+/// The time that timeouts and deadlines are measured with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeSource {
+    /// [Clock::now], which never goes backward. Depending on the platform it may not count
+    /// the time the machine is suspended.
+    Monotonic,
+    /// [Clock::system_time], which counts the time the machine is suspended, but follows
+    /// the adjustments of the system time. Time moved backward counts as none passed.
+    WallClock,
+}
+
+impl Default for TimeSource {
+    fn default() -> Self {
+        TimeSource::Monotonic
+    }
+}
+
+/// A point in time on both time sources, to measure the time elapsed since with either.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Stamp {
+    instant: Instant,
+    system: SystemTime,
+}
+
+impl Stamp {
+    pub(crate) fn now(clock: &dyn Clock) -> Self {
+        Self {
+            instant: clock.now(),
+            system: clock.system_time(),
+        }
+    }
+
+    /// The time an elapsed duration ago, or as far back as it can be represented.
+    pub(crate) fn before(clock: &dyn Clock, elapsed: Duration) -> Self {
+        let now = Self::now(clock);
+        Self {
+            instant: now.instant.checked_sub(elapsed).unwrap_or(now.instant),
+            system: now.system.checked_sub(elapsed).unwrap_or(now.system),
+        }
+    }
+
+    pub(crate) fn elapsed(&self, clock: &dyn Clock, source: TimeSource) -> Duration {
+        match source {
+            TimeSource::Monotonic => clock.now().saturating_duration_since(self.instant),
+            TimeSource::WallClock => clock
+                .system_time()
+                .duration_since(self.system)
+                .unwrap_or_else(|_| Duration::from_secs(0)),
+        }
+    }
+}
+
+/// A handle to wake up blocking sleeps early from other threads, see
+/// [Clock::sleep_interruptible]. Clones wake up the same sleeps.
+#[derive(Clone, Default)]
+pub struct WakeHandle {
+    parked: Arc<Mutex<Vec<Thread>>>,
+}
+
+impl WakeHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wake up the threads sleeping on this handle, so they check if they were interrupted.
+    /// Change the state they check before calling this.
+    pub fn wake(&self) {
+        for thread in self.parked.lock().unwrap().iter() {
+            thread.unpark();
+        }
+    }
+
+    /// Park the current thread until the deadline or until interrupted.
+    fn park(&self, deadline: Instant, interrupted: &dyn Fn() -> bool) -> bool {
+        let current = thread::current();
+        // Registering before the first check means a wake up can't be missed, as unparking a
+        // thread that is not parked yet makes its next park return right away.
+        self.parked.lock().unwrap().push(current.clone());
+        let result = park_until(deadline, interrupted);
+        let mut parked = self.parked.lock().unwrap();
+        if let Some(i) = parked.iter().position(|t| t.id() == current.id()) {
+            parked.swap_remove(i);
+        }
+        result
+    }
+}
+
+/// The time a duration after `now`, or a century later for durations an [Instant] can't
+/// reach, e.g. the delay of a throttle with a rate of zero.
+pub(crate) fn deadline_after(now: Instant, duration: Duration) -> Instant {
+    const CENTURY: Duration = Duration::from_secs(100 * 365 * 24 * 3600);
+    now.checked_add(duration).unwrap_or_else(|| now + CENTURY)
+}
+
+/// Park the current thread until the deadline or until interrupted, ignoring spurious wake
+/// ups.
+fn park_until(deadline: Instant, interrupted: &dyn Fn() -> bool) -> bool {
+    loop {
+        if interrupted() {
+            return true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        // Without threads nothing can unpark this thread on WASI, but sleeping there is
+        // implemented with `poll_oneoff`.
+        if cfg!(target_os = "wasi") {
+            thread::sleep(deadline - now);
+        } else {
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+/// The default clock, using the system monotonic clock and the thread (or async runtime)
+/// timers.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// A shared instance of the system clock, as waiters store it.
+    pub fn shared() -> Arc<dyn Clock> {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    /// Parks the thread, so the sleep can be interrupted with a [WakeHandle]. This panics on
+    /// wasm32-unknown-unknown, where only async waits are supported. On WASI this sleeps
+    /// with `poll_oneoff`.
+    fn sleep(&self, duration: Duration) {
+        park_until(deadline_after(Instant::now(), duration), &|| false);
+    }
+
+    /// With the `wasm`, `tokio`, `async-std` or `smol` features, this uses the timer of that
+    /// runtime instead of the shared timer thread. Only the first enabled backend in that
+    /// order is used.
+    ///
+    /// WASI has no threads for the timer, so without a runtime feature the first poll
+    /// blocks the executor thread for the whole duration, like [Clock::sleep].
+    #[cfg(feature = "async")]
+    fn async_sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        // JS timers are not Send, but wasm is single threaded so they never leave the thread.
+        #[cfg(feature = "wasm")]
+        {
+            // setTimeout fires right away for delays that don't fit in an i32.
+            let millis = duration.as_millis().min(i32::MAX as u128) as u32;
+            Box::pin(send_wrapper::SendWrapper::new(
+                gloo_timers::future::TimeoutFuture::new(millis),
+            ))
+        }
+        #[cfg(all(feature = "tokio", not(feature = "wasm")))]
+        {
+            Box::pin(tokio_crate::time::sleep(duration))
+        }
+        #[cfg(all(feature = "async-std", not(any(feature = "wasm", feature = "tokio"))))]
+        {
+            Box::pin(async_std_crate::task::sleep(duration))
+        }
+        #[cfg(all(
+            feature = "smol",
+            not(any(feature = "wasm", feature = "tokio", feature = "async-std"))
+        ))]
+        {
+            Box::pin(async move {
+                async_io::Timer::after(duration).await;
+            })
+        }
+        #[cfg(not(any(
+            feature = "wasm",
+            feature = "tokio",
+            feature = "async-std",
+            feature = "smol"
+        )))]
+        {
+            Box::pin(future::ThrottleTimerFuture::new(duration))
+        }
+    }
+
+    /// Only the shared timer thread sleeps without boxing, the runtime timers are boxed. A
+    /// zero duration is ready right away, without a timer.
+    #[cfg(feature = "async")]
+    fn clock_sleep(&self, duration: Duration) -> ClockSleep {
+        if duration == Duration::from_secs(0) {
+            return ClockSleep::done();
+        }
+        #[cfg(not(any(
+            feature = "wasm",
+            feature = "tokio",
+            feature = "async-std",
+            feature = "smol"
+        )))]
+        {
+            ClockSleep(SleepKind::Timer(future::ThrottleTimerFuture::new(duration)))
+        }
+        #[cfg(any(
+            feature = "wasm",
+            feature = "tokio",
+            feature = "async-std",
+            feature = "smol"
+        ))]
+        {
+            ClockSleep::boxed(self.async_sleep(duration))
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "async",
+    not(any(
+        feature = "wasm",
+        feature = "tokio",
+        feature = "async-std",
+        feature = "smol"
+    ))
+))]
+mod future {
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Condvar, Mutex, Once};
+    use std::task::{Context, Poll, Waker};
+    use std::time::{Duration, Instant};
+
+    /// A Future that resolves when a time has passed.
+    /// This is based on [https://rust-lang.github.io/async-book/02_execution/03_wakeups.html],
+    /// but all futures share a single timer thread. The timer is only added on the first
+    /// poll, so a future that is never polled doesn't start the thread, and one whose
+    /// deadline already passed completes without it.
+    pub(super) struct ThrottleTimerFuture {
+        deadline: Instant,
+        shared_state: Option<Arc<Mutex<SharedState>>>,
+    }
+
+    /// Shared state between the future and the timer thread
+    struct SharedState {
+        /// Whether or not the sleep time has elapsed
+        completed: bool,
+
+        /// Whether the future was dropped before the sleep time elapsed, so the timer thread
+        /// can discard its timer.
+        cancelled: bool,
+
+        /// The waker for the task that `TimerFuture` is running on.
+        /// The thread can use this after setting `completed = true` to tell
+        /// `TimerFuture`'s task to wake up, see that `completed = true`, and
+        /// move forward.
+        waker: Option<Waker>,
+    }
+
+    impl Future for ThrottleTimerFuture {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let shared_state = match &self.shared_state {
+                Some(shared_state) => shared_state.clone(),
+                None => {
+                    let now = Instant::now();
+                    if self.deadline <= now {
+                        return Poll::Ready(());
+                    }
+                    // WASI can't start the timer thread, so the sleep blocks instead.
+                    if cfg!(target_os = "wasi") {
+                        std::thread::sleep(self.deadline - now);
+                        return Poll::Ready(());
+                    }
+                    let shared_state = Arc::new(Mutex::new(SharedState {
+                        completed: false,
+                        cancelled: false,
+                        waker: Some(cx.waker().clone()),
+                    }));
+                    TimerThread::get().add(Timer {
+                        deadline: self.deadline,
+                        shared_state: shared_state.clone(),
+                    });
+                    self.shared_state = Some(shared_state);
+                    return Poll::Pending;
+                }
+            };
+
+            // Look at the shared state to see if the timer has already completed.
+            let mut shared_state = shared_state.lock().unwrap();
+            if shared_state.completed {
+                Poll::Ready(())
+            } else {
+                // Set waker so that the thread can wake up the current task
+                // when the timer has completed, ensuring that the future is polled
+                // again and sees that `completed = true`.
+                //
+                // It's tempting to do this once rather than repeatedly cloning
+                // the waker each time. However, the `TimerFuture` can move between
+                // tasks on the executor, which could cause a stale waker pointing
+                // to the wrong task, preventing `TimerFuture` from waking up
+                // correctly.
+                //
+                // N.B. it's possible to check for this using the `Waker::will_wake`
+                // function, but we omit that here to keep things simple.
+                shared_state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    impl Drop for ThrottleTimerFuture {
+        fn drop(&mut self) {
+            if let Some(shared_state) = &self.shared_state {
+                let mut shared_state = shared_state.lock().unwrap();
+                if shared_state.completed {
+                    return;
+                }
+                shared_state.cancelled = true;
+                shared_state.waker = None;
+                drop(shared_state);
+                TimerThread::get().cancel();
+            }
+        }
+    }
+
+    impl ThrottleTimerFuture {
+        /// Create a new `TimerFuture` which will complete after the provided
+        /// timeout.
+        pub fn new(duration: Duration) -> Self {
+            ThrottleTimerFuture {
+                deadline: super::deadline_after(Instant::now(), duration),
+                shared_state: None,
+            }
+        }
+    }
+
+    /// The number of timers in the heap of the timer thread.
+    #[cfg(test)]
+    pub(crate) fn pending_timers() -> usize {
+        TimerThread::get().timers.lock().unwrap().heap.len()
+    }
+
+    /// A pending timer. Timers are ordered so the earliest deadline is at the top of the heap.
+    struct Timer {
+        deadline: Instant,
+        shared_state: Arc<Mutex<SharedState>>,
+    }
+    impl Timer {
+        fn is_cancelled(&self) -> bool {
+            self.shared_state.lock().unwrap().cancelled
+        }
+
+        fn complete(self) {
+            let mut shared_state = self.shared_state.lock().unwrap();
+            // Signal that the timer has completed and wake up the last
+            // task on which the future was polled, if one exists.
+            shared_state.completed = true;
+            if let Some(waker) = shared_state.waker.take() {
+                waker.wake()
+            }
+        }
+    }
+    impl PartialEq for Timer {
+        fn eq(&self, other: &Self) -> bool {
+            self.deadline == other.deadline
+        }
+    }
+    impl Eq for Timer {}
+    impl PartialOrd for Timer {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Timer {
+        fn cmp(&self, other: &Self) -> Ordering {
+            other.deadline.cmp(&self.deadline)
+        }
+    }
+
+    #[derive(Default)]
+    struct Timers {
+        heap: BinaryHeap<Timer>,
+        /// How many timers of the heap were cancelled, about.
+        cancelled: usize,
+    }
+
+    /// A single thread that completes all timers, started the first time a timer is added.
+    struct TimerThread {
+        timers: Mutex<Timers>,
+        condvar: Condvar,
+    }
+    impl TimerThread {
+        fn get() -> &'static TimerThread {
+            static INIT: Once = Once::new();
+            static mut INSTANCE: Option<&'static TimerThread> = None;
+
+            // Safety: INSTANCE is only written once, guarded by INIT.
+            unsafe {
+                INIT.call_once(|| {
+                    let instance: &'static TimerThread = Box::leak(Box::new(TimerThread {
+                        timers: Mutex::new(Timers::default()),
+                        condvar: Condvar::new(),
+                    }));
+                    std::thread::Builder::new()
+                        .name("garcon-timer".to_string())
+                        .spawn(move || instance.run())
+                        .expect("Could not start the timer thread.");
+                    INSTANCE = Some(instance);
+                });
+                INSTANCE.unwrap()
+            }
+        }
+
+        fn add(&self, timer: Timer) {
+            self.timers.lock().unwrap().heap.push(timer);
+            self.condvar.notify_one();
+        }
+
+        /// Count a cancelled timer, and drop the cancelled timers once they are most of the
+        /// heap, so dropped futures don't hold memory until their deadline.
+        fn cancel(&self) {
+            let mut timers = self.timers.lock().unwrap();
+            timers.cancelled += 1;
+            if timers.cancelled * 2 > timers.heap.len() {
+                let heap = std::mem::take(&mut timers.heap);
+                timers.heap = heap
+                    .into_vec()
+                    .into_iter()
+                    .filter(|timer| !timer.is_cancelled())
+                    .collect();
+                timers.cancelled = 0;
+            }
+        }
+
+        fn run(&self) {
+            let mut timers = self.timers.lock().unwrap();
+            loop {
+                let now = Instant::now();
+                timers = match timers.heap.peek() {
+                    None => self.condvar.wait(timers).unwrap(),
+                    Some(timer) if timer.is_cancelled() => {
+                        timers.heap.pop();
+                        timers.cancelled = timers.cancelled.saturating_sub(1);
+                        timers
+                    }
+                    Some(timer) if timer.deadline <= now => {
+                        timers.heap.pop().unwrap().complete();
+                        timers
+                    }
+                    Some(timer) => {
+                        let timeout = timer.deadline - now;
+                        self.condvar.wait_timeout(timers, timeout).unwrap().0
+                    }
+                };
+            }
+        }
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "async",
+    not(any(
+        feature = "wasm",
+        feature = "tokio",
+        feature = "async-std",
+        feature = "smol"
+    ))
+))]
+pub(crate) use future::pending_timers;
+
+/// A clock for tests. Time only moves forward when advanced manually, or when a waiter
+/// sleeps (which returns right away), so tests of waiters don't need to actually wait.
+/// Clones share the same time.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    base: Instant,
+    elapsed: Arc<std::sync::Mutex<Duration>>,
+    wall_base: Arc<std::sync::Mutex<SystemTime>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            elapsed: Arc::new(std::sync::Mutex::new(Duration::from_secs(0))),
+            wall_base: Arc::new(std::sync::Mutex::new(std::time::UNIX_EPOCH)),
+        }
+    }
+
+    /// Set the wall clock, like an adjustment of the system time. The monotonic time is
+    /// unchanged.
+    pub fn set_system_time(&self, time: SystemTime) {
+        let elapsed = self.elapsed();
+        *self.wall_base.lock().unwrap() =
+            time.checked_sub(elapsed).unwrap_or(std::time::UNIX_EPOCH);
+    }
+
+    /// Move the time forward.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// The time that passed since this clock was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+
+    /// A shared handle to this clock, to pass to [crate::Waiter::set_clock].
+    pub fn shared(&self) -> Arc<dyn Clock> {
+        Arc::new(self.clone())
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.base + self.elapsed()
+    }
+
+    /// The wall clock starts at the Unix epoch, see [MockClock::set_system_time].
+    fn system_time(&self) -> SystemTime {
+        *self.wall_base.lock().unwrap() + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+
+    fn spin(&self, duration: Duration) {
+        self.advance(duration)
+    }
+
+    /// Time only passes when advanced, so this is interrupted only if it already was.
+    fn sleep_interruptible(
+        &self,
+        duration: Duration,
+        _handle: &WakeHandle,
+        interrupted: &dyn Fn() -> bool,
+    ) -> bool {
+        if interrupted() {
+            return true;
+        }
+        self.advance(duration);
+        false
+    }
+
+    #[cfg(feature = "async")]
+    fn async_sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.advance(duration);
+        Box::pin(futures_util::future::ready(()))
+    }
+}
+
+/// Block the thread for the delay, or return the error right away.
+pub(crate) fn sleep(
+    delay: Result<Duration, WaiterError>,
+    clock: &dyn Clock,
+    waiter: &dyn Waiter,
+) -> Result<(), WaiterError> {
+    #[cfg(feature = "tracing")]
+    let span = crate::trace::wait_span(waiter, &delay);
+    #[cfg(feature = "tracing")]
+    let _enter = span.enter();
+    #[cfg(not(feature = "tracing"))]
+    let _ = waiter;
+
+    clock.sleep(delay?);
+    Ok(())
+}
+
+/// The future of a wait that doesn't need to be boxed, e.g. [crate::ThrottleWait]. It
+/// sleeps for the delay, or resolves right away with the error of the waiter.
+#[cfg(feature = "async")]
+pub struct WaitFuture {
+    sleep: ClockSleep,
+    error: Option<WaiterError>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(feature = "async")]
+impl WaitFuture {
+    pub(crate) fn new(
+        delay: Result<Duration, WaiterError>,
+        clock: &dyn Clock,
+        waiter: &dyn Waiter,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::wait_span(waiter, &delay);
+        #[cfg(not(feature = "tracing"))]
+        let _ = waiter;
+
+        let (sleep, error) = match delay {
+            Ok(delay) => (clock.clock_sleep(delay), None),
+            Err(e) => (ClockSleep::done(), Some(e)),
+        };
+        Self {
+            sleep,
+            error,
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for WaitFuture {
+    type Output = Result<(), WaiterError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(e) = self.error.take() {
+            return Poll::Ready(Err(e));
+        }
+        let this = &mut *self;
+        #[cfg(feature = "tracing")]
+        let _enter = this.span.enter();
+        Pin::new(&mut this.sleep).poll(cx).map(Ok)
+    }
+}
+
+/// A future that resolves after the delay, or right away with the error.
+#[cfg(feature = "async")]
+pub(crate) fn async_sleep(
+    delay: Result<Duration, WaiterError>,
+    clock: &dyn Clock,
+    waiter: &dyn Waiter,
+) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+    #[cfg(feature = "tracing")]
+    let span = crate::trace::wait_span(waiter, &delay);
+    #[cfg(not(feature = "tracing"))]
+    let _ = waiter;
+
+    match delay {
+        Ok(delay) => {
+            let sleep = clock.async_sleep(delay);
+            let future = async move {
+                sleep.await;
+                Ok(())
+            };
+            #[cfg(feature = "tracing")]
+            let future = tracing::Instrument::instrument(future, span);
+            Box::pin(future)
+        }
+        Err(e) => Box::pin(futures_util::future::err(e)),
+    }
+}
@@ -0,0 +1,232 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+use crate::{Delay, Waiter};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+struct SlotState<V> {
+    result: Option<V>,
+    done: bool,
+}
+
+struct Slot<V> {
+    state: Mutex<SlotState<V>>,
+    condvar: Condvar,
+    /// The wakers of the async followers.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+type Batches<K, V> = Arc<Mutex<HashMap<K, Arc<Slot<V>>>>>;
+
+/// Batches the requests for a key that arrive within a window, and resolves them all with a
+/// single call. The first request of a batch waits for the window, given by the next delay
+/// of a fresh clone of the waiter, then makes the call, while the other requests of the
+/// batch wait for its result. Requests arriving during the call start a new batch.
+///
+/// Clones share the same batches.
+pub struct Coalescer<K, V> {
+    batches: Batches<K, V>,
+    window: Box<dyn Waiter>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K, V> Clone for Coalescer<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            batches: self.batches.clone(),
+            window: self.window.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+enum Role<V> {
+    Leader(Arc<Slot<V>>),
+    Follower(Arc<Slot<V>>),
+}
+
+/// Completes the batch when the leader is done, even if its call panicked or its future was
+/// dropped. The followers of an abandoned batch retry on their own.
+struct BatchGuard<K: Eq + Hash, V> {
+    batches: Batches<K, V>,
+    key: K,
+    slot: Arc<Slot<V>>,
+}
+
+impl<K: Eq + Hash, V> BatchGuard<K, V> {
+    fn close(&self) {
+        let mut batches = self.batches.lock().unwrap();
+        if matches!(batches.get(&self.key), Some(slot) if Arc::ptr_eq(slot, &self.slot)) {
+            batches.remove(&self.key);
+        }
+    }
+
+    fn complete(self, value: V) {
+        self.slot.state.lock().unwrap().result = Some(value);
+    }
+}
+
+impl<K: Eq + Hash, V> Drop for BatchGuard<K, V> {
+    fn drop(&mut self) {
+        self.close();
+        self.slot.state.lock().unwrap().done = true;
+        #[cfg(feature = "async")]
+        self.slot.wakers.wake_all();
+        self.slot.condvar.notify_all();
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Coalescer<K, V> {
+    /// Batch the requests arriving within a fixed window.
+    pub fn new(window: Duration) -> Self {
+        Self::with_waiter(Delay::throttle(window))
+    }
+
+    /// Batch the requests arriving within the first delay of the waiter. A waiter that gives
+    /// up makes the call right away.
+    pub fn with_waiter<W: 'static + Waiter>(waiter: W) -> Self {
+        Self {
+            batches: Arc::new(Mutex::new(HashMap::new())),
+            window: Box::new(waiter),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.window.set_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// The number of batches waiting for their window or their call.
+    pub fn pending(&self) -> usize {
+        self.batches.lock().unwrap().len()
+    }
+
+    /// Resolve a request, joining the pending batch for the key or starting a new one.
+    pub fn get<F: FnOnce(&K) -> V>(&self, key: K, f: F) -> V {
+        let mut f = Some(f);
+        loop {
+            match self.join(&key) {
+                Role::Leader(slot) => {
+                    let guard = self.guard(&key, slot);
+                    self.clock.sleep(self.window());
+                    guard.close();
+                    let value = (f.take().unwrap())(&key);
+                    guard.complete(value.clone());
+                    return value;
+                }
+                Role::Follower(slot) => {
+                    let mut state = slot.state.lock().unwrap();
+                    while !state.done {
+                        state = slot.condvar.wait(state).unwrap();
+                    }
+                    if let Some(value) = &state.result {
+                        return value.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like [Coalescer::get], with an async call.
+    #[cfg(feature = "async")]
+    pub async fn get_async<F, Fut>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce(K) -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let mut f = Some(f);
+        loop {
+            match self.join(&key) {
+                Role::Leader(slot) => {
+                    let guard = self.guard(&key, slot);
+                    self.clock.async_sleep(self.window()).await;
+                    guard.close();
+                    let value = (f.take().unwrap())(key.clone()).await;
+                    guard.complete(value.clone());
+                    return value;
+                }
+                Role::Follower(slot) => {
+                    if let Some(value) = SlotWait::new(slot).await {
+                        return value;
+                    }
+                }
+            }
+        }
+    }
+
+    fn join(&self, key: &K) -> Role<V> {
+        let mut batches = self.batches.lock().unwrap();
+        if let Some(slot) = batches.get(key) {
+            return Role::Follower(slot.clone());
+        }
+        let slot = Arc::new(Slot {
+            state: Mutex::new(SlotState {
+                result: None,
+                done: false,
+            }),
+            condvar: Condvar::new(),
+            #[cfg(feature = "async")]
+            wakers: Wakers::default(),
+        });
+        batches.insert(key.clone(), slot.clone());
+        Role::Leader(slot)
+    }
+
+    fn guard(&self, key: &K, slot: Arc<Slot<V>>) -> BatchGuard<K, V> {
+        BatchGuard {
+            batches: self.batches.clone(),
+            key: key.clone(),
+            slot,
+        }
+    }
+
+    fn window(&self) -> Duration {
+        let mut waiter = self.window.clone();
+        waiter.start();
+        waiter.advance().unwrap_or_else(|_| Duration::from_secs(0))
+    }
+}
+
+#[cfg(feature = "async")]
+struct SlotWait<V> {
+    slot: Arc<Slot<V>>,
+    waker: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl<V> SlotWait<V> {
+    fn new(slot: Arc<Slot<V>>) -> Self {
+        let waker = slot.wakers.slot();
+        Self { slot, waker }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<V: Clone> Future for SlotWait<V> {
+    type Output = Option<V>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<V>> {
+        let slot = self.slot.clone();
+        let state = slot.state.lock().unwrap();
+        if state.done {
+            Poll::Ready(state.result.clone())
+        } else {
+            self.waker.register(cx.waker());
+            Poll::Pending
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{Delay, Waiter, WaiterError};
+use crate::{Delay, Waiter, WaiterError, WaiterState};
+use core::time::Duration;
 
 #[cfg(feature = "async")]
 use core::{future::Future, pin::Pin};
@@ -6,6 +7,11 @@ use core::{future::Future, pin::Pin};
 #[cfg(all(feature = "no_std", feature = "async"))]
 use alloc::boxed::Box;
 
+#[cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+#[cfg(not(feature = "no_std"))]
+use std::sync::Arc;
+
 #[derive(Clone)]
 pub struct DelayComposer {
     a: Delay,
@@ -31,6 +37,33 @@ impl Waiter for DelayComposer {
         self.b.wait()?;
         Ok(())
     }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Ok(self.a.advance()? + self.b.advance()?)
+    }
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.a.set_clock(clock.clone());
+        self.b.set_clock(clock);
+    }
+    fn attempts(&self) -> usize {
+        self.a.attempts().max(self.b.attempts())
+    }
+    fn elapsed(&self) -> Duration {
+        self.a.elapsed().max(self.b.elapsed())
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.a.next_delay()? + self.b.next_delay()?)
+    }
+    fn remaining(&self) -> Option<Duration> {
+        crate::min_remaining(self.a.remaining(), self.b.remaining())
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.a.resume(state)?;
+        self.b.resume(state)
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} + {}", self.a, self.b)
+    }
 
     #[cfg(feature = "async")]
     fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
@@ -40,3 +73,400 @@ impl Waiter for DelayComposer {
         )
     }
 }
+
+describe_waiter!(DelayComposer);
+
+/// A waiter that lets its inner waiter wait a maximum number of times, then times out.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxAttemptsWaiter<W: Waiter> {
+    inner: W,
+    max_attempts: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: Option<u64>,
+}
+impl<W: Waiter> MaxAttemptsWaiter<W> {
+    pub fn new(inner: W, max_attempts: u64) -> Self {
+        Self {
+            inner,
+            max_attempts,
+            attempts: None,
+        }
+    }
+
+    fn attempt(&mut self) -> Result<(), WaiterError> {
+        let attempts = self.attempts.ok_or(WaiterError::NotStarted)?;
+        if attempts >= self.max_attempts {
+            Err(WaiterError::Timeout {
+                elapsed: self.inner.elapsed(),
+                attempts: attempts as usize,
+            })
+        } else {
+            self.attempts = Some(attempts + 1);
+            Ok(())
+        }
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for MaxAttemptsWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.attempts.ok_or(WaiterError::NotStarted)?;
+        self.inner.restart()?;
+        self.attempts = Some(0);
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.inner.start();
+        self.attempts = Some(0);
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.attempt()?;
+        self.inner.wait()
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.attempt()?;
+        self.inner.advance()
+    }
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock)
+    }
+    fn attempts(&self) -> usize {
+        self.attempts.unwrap_or(0) as usize
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        if self.attempts? >= self.max_attempts {
+            None
+        } else {
+            self.inner.next_delay()
+        }
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)?;
+        self.attempts = Some(state.attempts as u64);
+        Ok(())
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.describe(f)?;
+        write!(f, ", max {} attempts", self.max_attempts)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        match self.attempt() {
+            Ok(()) => self.inner.async_wait(),
+            Err(e) => Box::pin(futures_util::future::err(e)),
+        }
+    }
+}
+
+describe_waiter!(MaxAttemptsWaiter<W>);
+
+/// A waiter that uses a first waiter until it fails (e.g. times out), then switches to a
+/// second one. The second waiter is started when switching.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SequenceWaiter<A: Waiter, B: Waiter> {
+    first: A,
+    second: B,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    switched: bool,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl<A: Waiter, B: Waiter> SequenceWaiter<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            switched: false,
+            #[cfg(not(feature = "no_std"))]
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Switch to the second waiter if the first one failed. Returns the result of the first
+    /// waiter if it should be used.
+    fn check<T>(&mut self, result: Result<T, WaiterError>) -> Option<Result<T, WaiterError>> {
+        match result {
+            Err(WaiterError::Timeout { .. }) => {
+                self.switched = true;
+                self.second.start();
+                None
+            }
+            other => Some(other),
+        }
+    }
+}
+impl<A, B> Waiter for SequenceWaiter<A, B>
+where
+    A: 'static + Waiter + Clone,
+    B: 'static + Waiter + Clone,
+{
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.first.restart()?;
+        self.switched = false;
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.first.start();
+        self.switched = false;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        if !self.switched {
+            let result = self.first.wait();
+            if let Some(result) = self.check(result) {
+                return result;
+            }
+        }
+        self.second.wait()
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        if !self.switched {
+            let result = self.first.advance();
+            if let Some(result) = self.check(result) {
+                return result;
+            }
+        }
+        self.second.advance()
+    }
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.first.set_clock(clock.clone());
+        self.second.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        if self.switched {
+            self.first.attempts() + self.second.attempts()
+        } else {
+            self.first.attempts()
+        }
+    }
+    fn elapsed(&self) -> Duration {
+        self.first.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        if self.switched {
+            self.second.next_delay()
+        } else {
+            self.first.next_delay()
+        }
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.first.describe(f)?;
+        f.write_str(" then ")?;
+        self.second.describe(f)
+    }
+
+    /// The first waiter is advanced synchronously so we know when to switch, and its delay
+    /// is then awaited.
+    #[cfg(all(feature = "async", not(feature = "no_std")))]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        if !self.switched {
+            let result = self.first.advance();
+            if let Some(result) = self.check(result) {
+                return crate::clock::async_sleep(result, self.clock.as_ref(), self);
+            }
+        }
+        self.second.async_wait()
+    }
+}
+
+describe_waiter!(SequenceWaiter<A, B>);
+
+/// Extension methods to combine waiters.
+pub trait WaiterExt: Waiter + Sized {
+    /// Use this waiter until it times out, then use the other one.
+    fn then<B: Waiter>(self, other: B) -> SequenceWaiter<Self, B> {
+        SequenceWaiter::new(self, other)
+    }
+
+    /// Wait like [Waiter::wait], but sleep with the given implementation instead of the
+    /// clock of the waiter. This is how to wait with `no_std`.
+    fn wait_with<S: crate::Sleep + ?Sized>(&mut self, sleeper: &mut S) -> Result<(), WaiterError> {
+        sleeper.sleep(self.advance()?);
+        Ok(())
+    }
+
+    /// Time out once this waiter has been started for longer than the budget, see
+    /// [crate::MaxElapsedWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn max_elapsed(self, budget: Duration) -> crate::MaxElapsedWaiter<Self> {
+        crate::MaxElapsedWaiter::new(self, budget)
+    }
+
+    /// Make the waits of this waiter interruptible with a [crate::CancelHandle].
+    #[cfg(not(feature = "no_std"))]
+    fn cancelable(self) -> crate::CancelableWaiter<Self> {
+        crate::CancelableWaiter::new(self)
+    }
+
+    /// Make the waits of this shared waiter return in the order they started, see
+    /// [crate::FairQueueWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn fair_queue(self) -> crate::FairQueueWaiter<Self> {
+        crate::FairQueueWaiter::new(self)
+    }
+
+    /// Let a [crate::Notifier] end the waits of this waiter early.
+    #[cfg(not(feature = "no_std"))]
+    fn notifiable(self) -> crate::NotifiableWaiter<Self> {
+        crate::NotifiableWaiter::new(self)
+    }
+
+    /// Make the waits of this waiter pausable with a [crate::PauseHandle].
+    #[cfg(not(feature = "no_std"))]
+    fn pausable(self) -> crate::PausableWaiter<Self> {
+        crate::PausableWaiter::new(self)
+    }
+
+    /// Let the operation override the next delay, see [crate::RetryAfterWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn with_retry_after(self) -> crate::RetryAfterWaiter<Self> {
+        crate::RetryAfterWaiter::new(self)
+    }
+
+    /// Call callbacks around the waits of this waiter, see [crate::ObservedWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn observed(self) -> crate::ObservedWaiter<Self> {
+        crate::ObservedWaiter::new(self)
+    }
+
+    /// Record how long the waits of this waiter last compared to their delay, see
+    /// [crate::StatsWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn with_stats(self) -> crate::StatsWaiter<Self> {
+        crate::StatsWaiter::new(self)
+    }
+
+    /// Spin at the end of each wait for precise delays, see [crate::PrecisionWaiter].
+    #[cfg(not(feature = "no_std"))]
+    fn precise(self) -> crate::PrecisionWaiter<Self> {
+        crate::PrecisionWaiter::new(self)
+    }
+
+    /// Record metrics about the waits of this waiter, see [crate::MeteredWaiter].
+    #[cfg(all(feature = "metrics", not(feature = "no_std")))]
+    fn metered<S: Into<std::borrow::Cow<'static, str>>>(
+        self,
+        policy: S,
+    ) -> crate::MeteredWaiter<Self> {
+        crate::MeteredWaiter::new(self, policy)
+    }
+
+    /// Use a clock instead of the system clock.
+    #[cfg(not(feature = "no_std"))]
+    fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.set_clock(clock);
+        self
+    }
+}
+impl<W: Waiter> WaiterExt for W {}
+
+macro_rules! merge_waiter {
+    ($(#[$meta:meta])* $name: ident, $merge: ident) => {
+        $(#[$meta])*
+        #[cfg(not(feature = "no_std"))]
+        #[derive(Clone)]
+        pub struct $name<A: Waiter, B: Waiter> {
+            a: A,
+            b: B,
+            clock: Arc<dyn Clock>,
+        }
+        #[cfg(not(feature = "no_std"))]
+        impl<A: Waiter, B: Waiter> $name<A, B> {
+            pub fn new(a: A, b: B) -> Self {
+                Self {
+                    a,
+                    b,
+                    clock: SystemClock::shared(),
+                }
+            }
+        }
+        #[cfg(not(feature = "no_std"))]
+        impl<A, B> Waiter for $name<A, B>
+        where
+            A: 'static + Waiter + Clone,
+            B: 'static + Waiter + Clone,
+        {
+            fn restart(&mut self) -> Result<(), WaiterError> {
+                self.a.restart()?;
+                self.b.restart()?;
+                Ok(())
+            }
+            fn start(&mut self) {
+                self.a.start();
+                self.b.start();
+            }
+            fn wait(&mut self) -> Result<(), WaiterError> {
+                crate::clock::sleep(
+                    self.advance(),
+                    self.clock.as_ref(),
+                    self)
+            }
+            fn advance(&mut self) -> Result<Duration, WaiterError> {
+                Ok(self.a.advance()?.$merge(self.b.advance()?))
+            }
+            fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+                self.a.set_clock(clock.clone());
+                self.b.set_clock(clock.clone());
+                self.clock = clock;
+            }
+            fn attempts(&self) -> usize {
+                self.a.attempts().max(self.b.attempts())
+            }
+            fn elapsed(&self) -> Duration {
+                self.a.elapsed().max(self.b.elapsed())
+            }
+            fn next_delay(&self) -> Option<Duration> {
+                Some(self.a.next_delay()?.$merge(self.b.next_delay()?))
+            }
+            fn remaining(&self) -> Option<Duration> {
+                crate::min_remaining(self.a.remaining(), self.b.remaining())
+            }
+            fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+                self.a.resume(state)?;
+                self.b.resume(state)
+            }
+            fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(concat!(stringify!($merge), "("))?;
+                self.a.describe(f)?;
+                f.write_str(", ")?;
+                self.b.describe(f)?;
+                f.write_str(")")
+            }
+
+            #[cfg(feature = "async")]
+            fn async_wait(
+                &mut self,
+            ) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+                crate::clock::async_sleep(
+                    self.advance(),
+                    self.clock.as_ref(),
+                    self)
+            }
+        }
+        #[cfg(not(feature = "no_std"))]
+        describe_waiter!($name<A, B>);
+    };
+}
+
+merge_waiter!(
+    /// A waiter that advances two waiters and waits for the shorter of their delays. Fails
+    /// if either of them fails.
+    MinOf,
+    min
+);
+merge_waiter!(
+    /// A waiter that advances two waiters and waits for the longer of their delays. Fails
+    /// if either of them fails. This can be used to enforce a minimum delay on a backoff.
+    MaxOf,
+    max
+);
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "cron", not(feature = "no_std")))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError};
+use chrono::{DateTime, Utc};
+use cron_crate::Schedule;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A waiter that waits until the next occurrence of a cron schedule, in UTC, e.g.
+/// `"0 0 3 * * *"` for 03:00 every day. It times out once the schedule has no occurrence
+/// left.
+#[derive(Clone)]
+pub struct CronWaiter {
+    schedule: Schedule,
+    attempts: usize,
+    clock: Arc<dyn Clock>,
+}
+impl CronWaiter {
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Parse a cron expression, see the `cron` crate for its syntax.
+    pub fn parse(expression: &str) -> Result<Self, cron_crate::error::Error> {
+        Schedule::from_str(expression).map(Self::new)
+    }
+
+    fn until_next(&self, now: SystemTime) -> Option<Duration> {
+        let next = self.schedule.after(&DateTime::<Utc>::from(now)).next()?;
+        Some(
+            SystemTime::from(next)
+                .duration_since(now)
+                .unwrap_or_else(|_| Duration::from_secs(0)),
+        )
+    }
+}
+impl Waiter for CronWaiter {
+    fn start(&mut self) {
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self
+            .until_next(self.clock.system_time())
+            .ok_or(WaiterError::Timeout {
+                elapsed: Duration::from_secs(0),
+                attempts: self.attempts,
+            })?;
+        self.attempts += 1;
+        Ok(delay)
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.until_next(self.clock.system_time())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cron({})", self.schedule)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(CronWaiter);
@@ -0,0 +1,163 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, Clock, SystemClock};
+use crate::{Delay, Waiter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+struct DebounceState {
+    waiter: Box<dyn Waiter>,
+    deadline: Option<Instant>,
+    pending: bool,
+}
+
+/// Coalesces bursts of triggers, and fires once the triggers stopped for a quiet period. The
+/// quiet period is the next delay of a waiter, which is advanced on every trigger and
+/// started again on every burst, so e.g. a backoff makes a long burst wait longer.
+///
+/// By default only the trailing edge fires, at the end of the quiet period. With
+/// [Debouncer::leading], the first trigger of a burst fires right away instead, and the
+/// trailing edge fires only when the burst had more triggers. Clones share the same state.
+#[derive(Clone)]
+pub struct Debouncer {
+    state: Arc<Mutex<DebounceState>>,
+    clock: Arc<dyn Clock>,
+    callback: Option<Arc<dyn Fn() + Send + Sync>>,
+    leading: bool,
+    trailing: bool,
+}
+
+impl Debouncer {
+    /// Debounce with a fixed quiet period.
+    pub fn new(quiet: Duration) -> Self {
+        Self::with_waiter(Delay::throttle(quiet))
+    }
+
+    /// Debounce with the quiet periods given by a waiter. A waiter that gives up ends the
+    /// burst on that trigger.
+    pub fn with_waiter<W: 'static + Waiter>(waiter: W) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(DebounceState {
+                waiter: Box::new(waiter),
+                deadline: None,
+                pending: false,
+            })),
+            clock: SystemClock::shared(),
+            callback: None,
+            leading: false,
+            trailing: true,
+        }
+    }
+
+    /// Whether to fire on the first trigger of a burst.
+    pub fn leading(mut self, leading: bool) -> Self {
+        self.leading = leading;
+        self
+    }
+
+    /// Whether to fire at the end of a burst.
+    pub fn trailing(mut self, trailing: bool) -> Self {
+        self.trailing = trailing;
+        self
+    }
+
+    /// A callback called on every edge that fires.
+    pub fn on_fire<F: 'static + Fn() + Send + Sync>(mut self, f: F) -> Self {
+        self.callback = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record a trigger. Returns whether the leading edge fired.
+    pub fn trigger(&self) -> bool {
+        // A trailing edge that is due but was not polled yet belongs to the previous burst.
+        self.poll();
+
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let new_burst = state.deadline.is_none();
+        if new_burst {
+            state.waiter.start();
+        }
+        let quiet = state
+            .waiter
+            .advance()
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        state.deadline = Some(deadline_after(now, quiet));
+
+        let fire = new_burst && self.leading;
+        state.pending = self.trailing && !fire;
+        drop(state);
+        if fire {
+            self.fire();
+        }
+        fire
+    }
+
+    /// Fire the trailing edge if the quiet period is over. Returns whether it fired.
+    pub fn poll(&self) -> bool {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        match state.deadline {
+            Some(deadline) if deadline <= now => {
+                state.deadline = None;
+                let fire = std::mem::replace(&mut state.pending, false);
+                drop(state);
+                if fire {
+                    self.fire();
+                }
+                fire
+            }
+            _ => false,
+        }
+    }
+
+    /// The time left until the trailing edge, if one is pending.
+    pub fn remaining(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        if !state.pending {
+            return None;
+        }
+        let deadline = state.deadline?;
+        Some(deadline.saturating_duration_since(self.clock.now()))
+    }
+
+    /// Block until the trailing edge fired, through this or another clone. Returns `false`
+    /// right away if no trailing edge is pending.
+    pub fn wait(&self) -> bool {
+        while let Some(remaining) = self.remaining() {
+            self.clock.sleep(remaining);
+            if self.poll() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Like [Debouncer::wait], without blocking the thread.
+    #[cfg(feature = "async")]
+    pub fn async_wait(&self) -> Pin<Box<dyn Future<Output = bool> + Send>> {
+        let this = self.clone();
+        Box::pin(async move {
+            while let Some(remaining) = this.remaining() {
+                this.clock.async_sleep(remaining).await;
+                if this.poll() {
+                    return true;
+                }
+            }
+            false
+        })
+    }
+
+    fn fire(&self) {
+        if let Some(callback) = &self.callback {
+            callback();
+        }
+    }
+}
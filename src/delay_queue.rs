@@ -0,0 +1,232 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, Clock, SystemClock};
+use crate::{Waiter, WaiterError};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+struct Entry<T> {
+    deadline: Instant,
+    seq: u64,
+    item: T,
+}
+
+// The heap is a max-heap, so entries are ordered by reverse deadline, then insertion order.
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.seq).cmp(&(self.deadline, self.seq))
+    }
+}
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<T> Eq for Entry<T> {}
+
+struct QueueState<T> {
+    heap: BinaryHeap<Entry<T>>,
+    seq: u64,
+}
+
+struct Shared<T> {
+    state: Mutex<QueueState<T>>,
+    condvar: Condvar,
+    /// The wakers of the streams waiting for an item.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A queue of items that become available once their deadline passed, in deadline order.
+/// Items can be taken with [DelayQueue::try_pop] and [DelayQueue::pop], or with the async
+/// feature the queue is a stream of its items as they become due, which never ends.
+///
+/// Clones share the same queue, so items can be inserted from one thread and taken from
+/// another.
+pub struct DelayQueue<T> {
+    shared: Arc<Shared<T>>,
+    clock: Arc<dyn Clock>,
+    #[cfg(feature = "async")]
+    timer: Option<(Instant, futures_util::future::BoxFuture<'static, ()>)>,
+    #[cfg(feature = "async")]
+    slot: WakerSlot,
+}
+
+impl<T> Clone for DelayQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            clock: self.clock.clone(),
+            #[cfg(feature = "async")]
+            timer: None,
+            #[cfg(feature = "async")]
+            slot: self.shared.wakers.slot(),
+        }
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DelayQueue<T> {
+    pub fn new() -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(QueueState {
+                heap: BinaryHeap::new(),
+                seq: 0,
+            }),
+            condvar: Condvar::new(),
+            #[cfg(feature = "async")]
+            wakers: Wakers::default(),
+        });
+        Self {
+            #[cfg(feature = "async")]
+            slot: shared.wakers.slot(),
+            shared,
+            clock: SystemClock::shared(),
+            #[cfg(feature = "async")]
+            timer: None,
+        }
+    }
+
+    /// Use a clock instead of the system clock for the deadlines.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Insert an item, available at the deadline.
+    pub fn insert_at(&self, item: T, deadline: Instant) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.seq += 1;
+        let seq = state.seq;
+        state.heap.push(Entry {
+            deadline,
+            seq,
+            item,
+        });
+        drop(state);
+        #[cfg(feature = "async")]
+        self.shared.wakers.wake_all();
+        self.shared.condvar.notify_all();
+    }
+
+    /// Insert an item, available after the delay.
+    pub fn insert(&self, item: T, delay: Duration) {
+        self.insert_at(item, deadline_after(self.clock.now(), delay));
+    }
+
+    /// Insert an item, available after the next delay of the waiter. This fails if the
+    /// waiter gave up, e.g. a retried job ran out of attempts.
+    pub fn insert_with<W: Waiter + ?Sized>(
+        &self,
+        item: T,
+        waiter: &mut W,
+    ) -> Result<(), WaiterError> {
+        let delay = waiter.advance()?;
+        self.insert(item, delay);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.state.lock().unwrap().heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The deadline of the next item.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let state = self.shared.state.lock().unwrap();
+        state.heap.peek().map(|entry| entry.deadline)
+    }
+
+    /// Take the next item if it is due.
+    pub fn try_pop(&self) -> Option<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        self.pop_due(&mut state)
+    }
+
+    /// Block until an item is due and take it. This sleeps on the system clock, as it also
+    /// needs to wake up when an item is inserted.
+    pub fn pop(&self) -> T {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(item) = self.pop_due(&mut state) {
+                return item;
+            }
+            state = match state.heap.peek() {
+                Some(entry) => {
+                    let timeout = entry.deadline.saturating_duration_since(self.clock.now());
+                    self.shared.condvar.wait_timeout(state, timeout).unwrap().0
+                }
+                None => self.shared.condvar.wait(state).unwrap(),
+            };
+        }
+    }
+
+    fn pop_due(&self, state: &mut QueueState<T>) -> Option<T> {
+        if state.heap.peek()?.deadline <= self.clock.now() {
+            state.heap.pop().map(|entry| entry.item)
+        } else {
+            None
+        }
+    }
+}
+
+// The timer is boxed, and nothing else is ever pinned.
+#[cfg(feature = "async")]
+impl<T> Unpin for DelayQueue<T> {}
+
+#[cfg(feature = "async")]
+impl<T> futures_util::stream::Stream for DelayQueue<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        loop {
+            let deadline = {
+                let mut state = this.shared.state.lock().unwrap();
+                if let Some(item) = this.pop_due(&mut state) {
+                    this.timer = None;
+                    return Poll::Ready(Some(item));
+                }
+                // Inserting an item wakes us up, in case its deadline is earlier.
+                this.slot.register(cx.waker());
+                match state.heap.peek() {
+                    Some(entry) => entry.deadline,
+                    None => return Poll::Pending,
+                }
+            };
+
+            let clock = &this.clock;
+            if this.timer.as_ref().map(|(d, _)| *d) != Some(deadline) {
+                let delay = deadline.saturating_duration_since(clock.now());
+                this.timer = Some((deadline, clock.async_sleep(delay)));
+            }
+            let (_, timer) = this.timer.as_mut().unwrap();
+            match timer.as_mut().poll(cx) {
+                Poll::Ready(()) => this.timer = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
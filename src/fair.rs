@@ -0,0 +1,206 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::collections::HashSet;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Default)]
+struct Queue {
+    next_ticket: u64,
+    /// The ticket whose turn it is to return.
+    serving: u64,
+    /// The tickets given up before their turn, e.g. by a dropped future.
+    abandoned: HashSet<u64>,
+
+    /// The wakers of the async waits waiting for their turn, by ticket.
+    #[cfg(feature = "async")]
+    wakers: HashMap<u64, Waker>,
+}
+
+#[derive(Default)]
+struct FairState {
+    queue: Mutex<Queue>,
+    turn: Condvar,
+}
+
+impl FairState {
+    /// Let the next ticket that wasn't abandoned return. The queue must be locked.
+    fn next_turn(&self, queue: &mut Queue) {
+        queue.serving += 1;
+        while queue.abandoned.remove(&queue.serving) {
+            queue.serving += 1;
+        }
+        #[cfg(feature = "async")]
+        if let Some(waker) = queue.wakers.remove(&queue.serving) {
+            waker.wake();
+        }
+        self.turn.notify_all();
+    }
+}
+
+/// A place in the queue of a [FairQueueWaiter], given up on drop if its turn didn't come.
+struct Ticket {
+    state: Arc<FairState>,
+    id: u64,
+    done: bool,
+}
+
+impl Ticket {
+    /// Block until it is the turn of this ticket, and let the next one go.
+    fn wait_turn(mut self) {
+        let mut queue = self.state.queue.lock().unwrap();
+        while queue.serving != self.id {
+            queue = self.state.turn.wait(queue).unwrap();
+        }
+        self.state.next_turn(&mut queue);
+        self.done = true;
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut queue = self.state.queue.lock().unwrap();
+        if queue.serving == self.id {
+            self.state.next_turn(&mut queue);
+        } else {
+            queue.abandoned.insert(self.id);
+        }
+        #[cfg(feature = "async")]
+        queue.wakers.remove(&self.id);
+    }
+}
+
+/// A future that resolves when it is the turn of its ticket.
+#[cfg(feature = "async")]
+struct Turn {
+    ticket: Ticket,
+}
+
+#[cfg(feature = "async")]
+impl Future for Turn {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = self.ticket.state.clone();
+        let mut queue = state.queue.lock().unwrap();
+        if queue.serving == self.ticket.id {
+            state.next_turn(&mut queue);
+            self.ticket.done = true;
+            Poll::Ready(())
+        } else {
+            queue.wakers.insert(self.ticket.id, cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// A waiter whose waits return in the order they started, for rate limiters shared by many
+/// threads or tasks: a wait that slept for its delay still waits for the earlier waits to
+/// return, so admission is first come, first served instead of whoever wakes up first.
+///
+/// Clones share the same queue, so wrap the shared limiter once and give a clone of this
+/// waiter to each thread or task. A wait that is dropped, or whose waiter gave up, leaves
+/// the queue.
+#[derive(Clone)]
+pub struct FairQueueWaiter<W: Waiter> {
+    inner: W,
+    state: Arc<FairState>,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> FairQueueWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            state: Arc::new(FairState::default()),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Take the delay of the inner waiter and a place in the queue together, so the order
+    /// of the queue is the order of the delays.
+    fn enqueue(&mut self) -> Result<(Duration, Ticket), WaiterError> {
+        let mut queue = self.state.queue.lock().unwrap();
+        let delay = self.inner.advance()?;
+        let id = queue.next_ticket;
+        queue.next_ticket += 1;
+        let ticket = Ticket {
+            state: self.state.clone(),
+            id,
+            done: false,
+        };
+        Ok((delay, ticket))
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for FairQueueWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let enqueued = self.enqueue();
+        let delay = enqueued.as_ref().map(|(delay, _)| *delay).map_err(|e| *e);
+        sleep(delay, self.clock.as_ref(), self)?;
+        if let Ok((_, ticket)) = enqueued {
+            ticket.wait_turn();
+        }
+        Ok(())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        f.write_str(", in a fair queue")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        let (delay, ticket) = match self.enqueue() {
+            Ok(enqueued) => enqueued,
+            Err(e) => return Box::pin(futures_util::future::err(e)),
+        };
+        let sleep = async_sleep(Ok(delay), self.clock.as_ref(), self);
+        Box::pin(async move {
+            let turn = Turn { ticket };
+            sleep.await?;
+            turn.await;
+            Ok(())
+        })
+    }
+}
+
+describe_waiter!(FairQueueWaiter<W>);
@@ -0,0 +1,129 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock, WakeHandle};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A handle to give a [RetryAfterWaiter] the delay a server asked for, e.g. from an HTTP
+/// `Retry-After` header.
+#[derive(Clone, Default)]
+pub struct RetryAfterHint {
+    next: Arc<Mutex<Option<Duration>>>,
+    wake: WakeHandle,
+}
+
+impl RetryAfterHint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for this duration instead of the computed delay, on the next wait only. A
+    /// blocking wait in progress is cut short to wait for this duration from now instead.
+    pub fn hint(&self, delay: Duration) {
+        *self.next.lock().unwrap() = Some(delay);
+        self.wake.wake();
+    }
+
+    fn take(&self) -> Option<Duration> {
+        self.next.lock().unwrap().take()
+    }
+
+    fn is_set(&self) -> bool {
+        self.next.lock().unwrap().is_some()
+    }
+}
+
+/// A waiter that waits for the hinted delay when there is one, instead of the delay of its
+/// inner waiter. The inner waiter is still advanced, so it still counts attempts and can
+/// time out.
+#[derive(Clone)]
+pub struct RetryAfterWaiter<W: Waiter> {
+    inner: W,
+    hint: RetryAfterHint,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> RetryAfterWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_hint(inner, RetryAfterHint::new())
+    }
+
+    /// Create a waiter taking its hints from an existing handle.
+    pub fn with_hint(inner: W, hint: RetryAfterHint) -> Self {
+        Self {
+            inner,
+            hint,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn hint(&self) -> RetryAfterHint {
+        self.hint.clone()
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for RetryAfterWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.hint.take();
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.hint.take();
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let delay = self.advance();
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::wait_span(self, &delay);
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        let mut delay = delay?;
+        let hint = &self.hint;
+        while self
+            .clock
+            .sleep_interruptible(delay, &hint.wake, &|| hint.is_set())
+        {
+            delay = hint.take().unwrap_or_else(|| Duration::from_secs(0));
+        }
+        Ok(())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.inner.advance()?;
+        Ok(self.hint.take().unwrap_or(delay))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        let hint = *self.hint.next.lock().unwrap();
+        hint.or_else(|| self.inner.next_delay())
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        f.write_str(", with retry after hints")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(RetryAfterWaiter<W>);
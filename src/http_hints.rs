@@ -0,0 +1,136 @@
+#![cfg(all(feature = "http-hints", not(feature = "no_std")))]
+use crate::clock::{deadline_after, sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// The quota advertised by a server in the `RateLimit-Limit`, `RateLimit-Remaining` and
+/// `RateLimit-Reset` headers of the IETF draft "RateLimit Header Fields for HTTP".
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitHeaders {
+    /// The number of requests allowed in the window.
+    pub limit: u64,
+    /// The number of requests left in the window.
+    pub remaining: u64,
+    /// The time until the window resets.
+    pub reset: Duration,
+}
+
+/// A header value starts with the number, and may be followed by a list of policies.
+fn parse_number(value: &str) -> Option<u64> {
+    value.split(&[',', ';'][..]).next()?.trim().parse().ok()
+}
+
+impl RateLimitHeaders {
+    /// Parse the values of the three headers.
+    pub fn parse(limit: &str, remaining: &str, reset: &str) -> Option<Self> {
+        Some(Self {
+            limit: parse_number(limit)?,
+            remaining: parse_number(remaining)?,
+            reset: Duration::from_secs(parse_number(reset)?),
+        })
+    }
+
+    /// Find the headers, ignoring the case of their names. Returns None if any of them is
+    /// missing or invalid.
+    pub fn from_headers<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(
+        headers: I,
+    ) -> Option<Self> {
+        let (mut limit, mut remaining, mut reset) = (None, None, None);
+        for (name, value) in headers {
+            if name.eq_ignore_ascii_case("ratelimit-limit") {
+                limit = Some(value);
+            } else if name.eq_ignore_ascii_case("ratelimit-remaining") {
+                remaining = Some(value);
+            } else if name.eq_ignore_ascii_case("ratelimit-reset") {
+                reset = Some(value);
+            }
+        }
+        Self::parse(limit?, remaining?, reset?)
+    }
+}
+
+struct Budget {
+    remaining: u64,
+    reset_at: Instant,
+}
+
+/// A waiter that paces waits to the quota advertised by a server. After each response, pass
+/// its headers to [RateLimitWaiter::update]; the remaining requests are then spread evenly
+/// until the window resets, and once none are left the waiter waits for the reset. Without
+/// a known quota, it doesn't wait.
+///
+/// Clones share the same quota.
+#[derive(Clone)]
+pub struct RateLimitWaiter {
+    budget: Arc<Mutex<Option<Budget>>>,
+    clock: Arc<dyn Clock>,
+}
+impl RateLimitWaiter {
+    pub fn new() -> Self {
+        Self {
+            budget: Arc::new(Mutex::new(None)),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn update(&self, headers: &RateLimitHeaders) {
+        *self.budget.lock().unwrap() = Some(Budget {
+            remaining: headers.remaining,
+            reset_at: deadline_after(self.clock.now(), headers.reset),
+        });
+    }
+}
+impl Default for RateLimitWaiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Waiter for RateLimitWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self.clock.now();
+        let mut budget = self.budget.lock().unwrap();
+        let delay = match budget.as_mut() {
+            Some(b) if now < b.reset_at => {
+                let left = b.reset_at - now;
+                if b.remaining == 0 {
+                    left
+                } else {
+                    let delay = left / u32::try_from(b.remaining).unwrap_or(u32::MAX);
+                    b.remaining -= 1;
+                    return Ok(delay);
+                }
+            }
+            _ => Duration::from_secs(0),
+        };
+        // The window is over (or will be once we waited), so the quota is unknown.
+        *budget = None;
+        Ok(delay)
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("rate_limit_headers")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(RateLimitWaiter);
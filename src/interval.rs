@@ -0,0 +1,117 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// What an [Interval] does when a wait starts after its tick was due.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MissedTickBehavior {
+    /// Return right away for each missed tick, until the schedule is caught up.
+    Burst,
+    /// Return right away, and schedule the next ticks from now.
+    Delay,
+    /// Skip the missed ticks, and wait for the next tick of the original schedule.
+    Skip,
+}
+
+/// A waiter ticking at a fixed rate. Each wait returns at the next tick, so the time spent
+/// between waits is taken out of the wait, and a loop doesn't drift like it would with a
+/// [crate::ThrottleWaiter]. The first tick is one period after the start.
+#[derive(Clone)]
+pub struct Interval {
+    period: Duration,
+    behavior: MissedTickBehavior,
+    next: Option<Instant>,
+    ticks: usize,
+    clock: Arc<dyn Clock>,
+}
+impl Interval {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            behavior: MissedTickBehavior::Burst,
+            next: None,
+            ticks: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Set what to do when ticks are missed. The default is [MissedTickBehavior::Burst].
+    pub fn with_missed_tick_behavior(mut self, behavior: MissedTickBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+
+    fn tick(&mut self) -> Result<Duration, WaiterError> {
+        let next = self.next.ok_or(WaiterError::NotStarted)?;
+        let now = self.clock.now();
+        self.ticks += 1;
+        if now <= next {
+            self.next = Some(next + self.period);
+            return Ok(next - now);
+        }
+        let (delay, next) = match self.behavior {
+            MissedTickBehavior::Burst => (Duration::from_secs(0), next + self.period),
+            MissedTickBehavior::Delay => (Duration::from_secs(0), now + self.period),
+            MissedTickBehavior::Skip => {
+                let period = self.period.as_nanos().max(1);
+                let missed = (now - next).as_nanos() / period + 1;
+                let tick = next + Duration::from_nanos((missed * period) as u64);
+                (tick - now, tick + self.period)
+            }
+        };
+        self.next = Some(next);
+        Ok(delay)
+    }
+}
+impl Waiter for Interval {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.next.ok_or(WaiterError::NotStarted)?;
+        self.start();
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.next = Some(self.clock.now() + self.period);
+        self.ticks = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.tick(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.tick()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.ticks
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        let next = self.next?;
+        Some(next.saturating_duration_since(self.clock.now()))
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.behavior {
+            MissedTickBehavior::Burst => write!(f, "interval({:?})", self.period),
+            MissedTickBehavior::Delay => write!(f, "interval({:?}, delay missed)", self.period),
+            MissedTickBehavior::Skip => write!(f, "interval({:?}, skip missed)", self.period),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.tick(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(Interval);
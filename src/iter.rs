@@ -0,0 +1,48 @@
+use crate::{retry, Waiter};
+
+/// An iterator calling a fallible function on each item of an inner iterator, retrying
+/// each item with the waiter. The waiter is started again for each item, so every item gets
+/// the whole schedule; use a waiter with shared state (e.g. a [crate::TokenBucketWaiter])
+/// to also pace the items together.
+pub struct MapRetry<I, W, F> {
+    iter: I,
+    waiter: W,
+    f: F,
+}
+
+impl<I, W, F, T, E> Iterator for MapRetry<I, W, F>
+where
+    I: Iterator,
+    W: Waiter,
+    F: FnMut(&I::Item) -> Result<T, E>,
+{
+    /// The first success for the item, or its last error once the waiter gave up.
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        let f = &mut self.f;
+        Some(retry(&mut self.waiter, || f(&item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Extension methods to retry the items of iterators.
+pub trait RetryIteratorExt: Iterator + Sized {
+    /// Map each item with a fallible function, retried with the waiter, see [MapRetry].
+    fn map_retry<W, F, T, E>(self, waiter: W, f: F) -> MapRetry<Self, W, F>
+    where
+        W: Waiter,
+        F: FnMut(&Self::Item) -> Result<T, E>,
+    {
+        MapRetry {
+            iter: self,
+            waiter,
+            f,
+        }
+    }
+}
+impl<I: Iterator> RetryIteratorExt for I {}
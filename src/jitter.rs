@@ -0,0 +1,173 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::random::{Rng, RngSource};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// How a [JitterWaiter] randomizes the delays of its inner waiter.
+/// See [https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Jitter {
+    /// Wait a random time between zero and the delay.
+    Full,
+    /// Wait half the delay, plus a random time between zero and the other half.
+    Equal,
+}
+
+impl Jitter {
+    fn apply(self, delay: Duration, rng: &RngSource) -> Duration {
+        match self {
+            Jitter::Full => rng.between(Duration::from_secs(0), delay),
+            Jitter::Equal => {
+                let half = delay / 2;
+                half + rng.between(Duration::from_secs(0), delay - half)
+            }
+        }
+    }
+}
+
+/// A waiter that randomizes the delays of any other waiter, so multiple threads or
+/// processes retrying at the same time don't stay synchronized.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JitterWaiter<W: Waiter> {
+    inner: W,
+    jitter: Jitter,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: RngSource,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> JitterWaiter<W> {
+    pub fn new(inner: W, jitter: Jitter) -> Self {
+        Self {
+            inner,
+            jitter,
+            rng: RngSource::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Use a generator for the random delays, e.g. a [crate::SeededRng] to reproduce them.
+    /// Clones share the generator.
+    pub fn with_rng<R: 'static + Rng>(mut self, rng: R) -> Self {
+        self.rng = RngSource::new(rng);
+        self
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for JitterWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Ok(self.jitter.apply(self.inner.advance()?, &self.rng))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        match self.jitter {
+            Jitter::Full => f.write_str(" with full jitter"),
+            Jitter::Equal => f.write_str(" with equal jitter"),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(JitterWaiter<W>);
+
+/// A waiter that waits a uniformly random time between min and max (inclusive) every time.
+/// This is the simplest way to keep many pollers from waking up at the same time.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RandomWaiter {
+    min: Duration,
+    max: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: RngSource,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
+}
+impl RandomWaiter {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            rng: RngSource::default(),
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Use a generator for the random delays, see [JitterWaiter::with_rng].
+    pub fn with_rng<R: 'static + Rng>(mut self, rng: R) -> Self {
+        self.rng = RngSource::new(rng);
+        self
+    }
+}
+impl Waiter for RandomWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.attempts = 0;
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.attempts += 1;
+        Ok(self.rng.between(self.min, self.max))
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "random({:?}..={:?})", self.min, self.max)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(RandomWaiter);
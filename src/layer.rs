@@ -0,0 +1,132 @@
+#![cfg(all(feature = "tower", not(feature = "no_std")))]
+use crate::clock::{async_sleep, Clock, SystemClock};
+use crate::{RetryDecision, Waiter, WaiterError};
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_crate::retry::Policy;
+use tower_crate::{Layer, Service};
+
+/// The error of a [DelayService].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DelayError<E> {
+    /// The waiter gave up, the request was not sent.
+    Waiter(WaiterError),
+    /// The inner service failed.
+    Service(E),
+}
+
+/// A tower layer delaying each request through a service with a waiter, e.g. a
+/// [crate::TokenBucketWaiter] for client side rate limiting. Each service built by the
+/// layer gets a clone of the waiter.
+#[derive(Clone)]
+pub struct DelayLayer<W: Waiter> {
+    waiter: W,
+}
+impl<W: Waiter> DelayLayer<W> {
+    pub fn new(waiter: W) -> Self {
+        Self { waiter }
+    }
+}
+impl<S, W: Waiter + Clone> Layer<S> for DelayLayer<W> {
+    type Service = DelayService<S, W>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DelayService::new(inner, self.waiter.clone())
+    }
+}
+
+/// A service that waits with its waiter before sending each request to the inner service.
+#[derive(Clone)]
+pub struct DelayService<S, W: Waiter> {
+    inner: S,
+    waiter: W,
+}
+impl<S, W: Waiter> DelayService<S, W> {
+    /// The waiter is started right away.
+    pub fn new(inner: S, mut waiter: W) -> Self {
+        waiter.start();
+        Self { inner, waiter }
+    }
+}
+impl<S, W, Request> Service<Request> for DelayService<S, W>
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Future: Send,
+    W: Waiter,
+    Request: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = DelayError<S::Error>;
+    type Future = BoxFuture<'static, Result<S::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(DelayError::Service)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let wait = self.waiter.async_wait();
+        // Send the request with the service that was polled ready, and keep a clone.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            wait.await.map_err(DelayError::Waiter)?;
+            inner.call(request).await.map_err(DelayError::Service)
+        })
+    }
+}
+
+/// A tower retry policy, retrying the errors for which the classifier returns
+/// [RetryDecision::Retry] after the delay of the waiter, until the waiter gives up.
+/// Successful responses are never retried.
+#[derive(Clone)]
+pub struct RetryPolicy<W: Waiter, C> {
+    waiter: W,
+    classify: C,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter, C> RetryPolicy<W, C> {
+    /// The waiter is started right away.
+    pub fn new(mut waiter: W, classify: C) -> Self {
+        waiter.start();
+        Self {
+            waiter,
+            classify,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Use a clock instead of the system clock, for the policy and its waiter.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.waiter.set_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+}
+impl<W, C, Request, Response, E> Policy<Request, Response, E> for RetryPolicy<W, C>
+where
+    W: 'static + Waiter + Clone,
+    C: 'static + Fn(&E) -> RetryDecision + Clone + Send,
+    Request: Clone,
+{
+    type Future = BoxFuture<'static, Self>;
+
+    fn retry(&self, _: &Request, result: Result<&Response, &E>) -> Option<Self::Future> {
+        let error = result.err()?;
+        if (self.classify)(error) == RetryDecision::Abort {
+            return None;
+        }
+        // The next attempt uses an advanced copy of this policy.
+        let mut next = self.clone();
+        let delay = next.waiter.advance().ok()?;
+        let wait = async_sleep(Ok(delay), next.clock.as_ref(), &next.waiter);
+        Some(Box::pin(async move {
+            let _ = wait.await;
+            next
+        }))
+    }
+
+    fn clone_request(&self, request: &Request) -> Option<Request> {
+        Some(request.clone())
+    }
+}
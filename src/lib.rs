@@ -2,28 +2,290 @@
 extern crate alloc;
 
 use alloc::boxed::Box;
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
 
 #[cfg(not(feature = "no_std"))]
-use core::time::Duration;
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
 
 #[cfg(feature = "async")]
 use core::{future::Future, pin::Pin};
 
+/// Implement Display and Debug for a waiter, with its [Waiter::describe].
+macro_rules! describe_waiter {
+    ($name:ident $(<$($param:ident),+>)?) => {
+        impl$(<$($param: 'static + crate::Waiter + Clone),+>)? core::fmt::Display
+            for $name$(<$($param),+>)?
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                crate::Waiter::describe(self, f)
+            }
+        }
+        impl$(<$($param: 'static + crate::Waiter + Clone),+>)? core::fmt::Debug
+            for $name$(<$($param),+>)?
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                crate::Waiter::describe(self, f)
+            }
+        }
+    };
+}
+
+/// The earliest of two time limits.
+fn min_remaining(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Write the cap of a backoff, unless it is the maximum duration used for no cap.
+fn describe_cap(f: &mut core::fmt::Formatter<'_>, cap: Duration) -> core::fmt::Result {
+    if cap < Duration::from_nanos(u64::MAX) {
+        write!(f, " ≤ {:?}", cap)?;
+    }
+    Ok(())
+}
+
 #[cfg(not(feature = "no_std"))]
-mod throttle;
+mod breaker;
+#[cfg(not(feature = "no_std"))]
+pub use breaker::{CircuitBreaker, CircuitError, CircuitState};
+
+#[cfg(not(feature = "no_std"))]
+mod budget;
+#[cfg(not(feature = "no_std"))]
+pub use budget::{BudgetError, RetryBudget};
+
+#[cfg(not(feature = "no_std"))]
+mod bulkhead;
+#[cfg(not(feature = "no_std"))]
+pub use bulkhead::{Bulkhead, BulkheadPermit};
+
+#[cfg(not(feature = "no_std"))]
+mod cancel;
 #[cfg(not(feature = "no_std"))]
-pub use throttle::ExponentialBackoffWaiter;
+pub use cancel::{CancelHandle, CancelableWaiter, ShutdownSignal};
+
+#[cfg(not(feature = "no_std"))]
+mod channel;
+#[cfg(not(feature = "no_std"))]
+pub use channel::WaiterRecvExt;
+
+#[cfg(not(feature = "no_std"))]
+mod clock;
+#[cfg(all(not(feature = "no_std"), any(test, feature = "test-util")))]
+pub use clock::MockClock;
+
 #[cfg(not(feature = "no_std"))]
+pub use clock::{Clock, SystemClock, TimeSource, WakeHandle};
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub use clock::{ClockSleep, WaitFuture};
+
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+pub use stream::{ThrottledStream, WaiterStreamExt};
+
+mod throttle;
 pub use throttle::ThrottleWaiter;
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub use throttle::{BackoffWait, ThrottleWait};
+pub use throttle::{CapBehavior, ExponentialBackoffWaiter};
+
+#[cfg(not(feature = "no_std"))]
+mod backoff;
+#[cfg(not(feature = "no_std"))]
+pub use backoff::{
+    AimdWaiter, DecorrelatedJitterWaiter, FibonacciBackoffWaiter, FixedScheduleWaiter,
+    FnBackoffWaiter, GradientWaiter, HarmonicBackoffWaiter, LatencyThrottleWaiter,
+    LinearBackoffWaiter, LogarithmicBackoffWaiter, PolynomialBackoffWaiter, WarmupWaiter,
+};
+
+#[cfg(not(feature = "no_std"))]
+mod coalesce;
+#[cfg(not(feature = "no_std"))]
+pub use coalesce::Coalescer;
+
+#[cfg(all(feature = "cron", not(feature = "no_std")))]
+mod cron;
+#[cfg(all(feature = "cron", not(feature = "no_std")))]
+pub use cron::CronWaiter;
+
+#[cfg(not(feature = "no_std"))]
+mod debounce;
+#[cfg(not(feature = "no_std"))]
+pub use debounce::Debouncer;
+
+#[cfg(not(feature = "no_std"))]
+mod delay_queue;
+#[cfg(not(feature = "no_std"))]
+pub use delay_queue::DelayQueue;
+
+#[cfg(not(feature = "no_std"))]
+mod fair;
+#[cfg(not(feature = "no_std"))]
+pub use fair::FairQueueWaiter;
+
+#[cfg(not(feature = "no_std"))]
+mod hint;
+#[cfg(not(feature = "no_std"))]
+pub use hint::{RetryAfterHint, RetryAfterWaiter};
+
+#[cfg(all(feature = "http-hints", not(feature = "no_std")))]
+mod http_hints;
+#[cfg(all(feature = "http-hints", not(feature = "no_std")))]
+pub use http_hints::{RateLimitHeaders, RateLimitWaiter};
+
+#[cfg(not(feature = "no_std"))]
+mod interval;
+#[cfg(not(feature = "no_std"))]
+pub use interval::{Interval, MissedTickBehavior};
+
+mod iter;
+pub use iter::{MapRetry, RetryIteratorExt};
+
+#[cfg(not(feature = "no_std"))]
+mod jitter;
+#[cfg(not(feature = "no_std"))]
+pub use jitter::{Jitter, JitterWaiter, RandomWaiter};
+
+#[cfg(all(feature = "tower", not(feature = "no_std")))]
+mod layer;
+#[cfg(all(feature = "tower", not(feature = "no_std")))]
+pub use layer::{DelayError, DelayLayer, DelayService, RetryPolicy};
+
+#[cfg(all(feature = "metrics", not(feature = "no_std")))]
+mod metered;
+#[cfg(all(feature = "metrics", not(feature = "no_std")))]
+pub use metered::MeteredWaiter;
+
+#[cfg(not(feature = "no_std"))]
+mod notify;
+#[cfg(not(feature = "no_std"))]
+pub use notify::{NotifiableWaiter, Notifier, Wakeup};
+
+#[cfg(not(feature = "no_std"))]
+mod observe;
+#[cfg(not(feature = "no_std"))]
+pub use observe::ObservedWaiter;
+
+#[cfg(not(feature = "no_std"))]
+mod pause;
+#[cfg(not(feature = "no_std"))]
+pub use pause::{PausableWaiter, PauseHandle};
+
+#[cfg(not(feature = "no_std"))]
+mod policy;
+#[cfg(not(feature = "no_std"))]
+pub use policy::DelayPolicy;
+
+#[cfg(not(feature = "no_std"))]
+mod random;
+#[cfg(not(feature = "no_std"))]
+pub use random::{Rng, SeededRng};
+
+#[cfg(not(feature = "no_std"))]
+mod precision;
+#[cfg(not(feature = "no_std"))]
+pub use precision::PrecisionWaiter;
+
+#[cfg(not(feature = "no_std"))]
+mod ratelimit;
+#[cfg(not(feature = "no_std"))]
+pub use ratelimit::{GcraWaiter, LeakyBucketWaiter, SlidingWindowWaiter, TokenBucketWaiter};
+#[cfg(not(feature = "no_std"))]
+pub use ratelimit::{Priority, PriorityWaiter};
+
+mod sleep;
+#[cfg(feature = "embedded-hal")]
+pub use sleep::HalDelay;
+pub use sleep::Sleep;
+#[cfg(all(feature = "async", feature = "no_std"))]
+pub use sleep::{set_async_sleep, AsyncSleepFn};
+#[cfg(feature = "no_std")]
+pub use sleep::{set_sleep, SleepFn};
+
+#[cfg(not(feature = "no_std"))]
+mod registry;
+#[cfg(not(feature = "no_std"))]
+pub use registry::BackoffMap;
+
+#[cfg(not(feature = "no_std"))]
+mod semaphore;
+#[cfg(not(feature = "no_std"))]
+pub use semaphore::{Semaphore, SemaphorePermit, SemaphoreWaiter};
+
+#[cfg(not(feature = "no_std"))]
+mod shared;
+#[cfg(not(feature = "no_std"))]
+pub use shared::SharedBackoff;
+
+#[cfg(not(feature = "no_std"))]
+mod stats;
+#[cfg(not(feature = "no_std"))]
+pub use stats::{StatsWaiter, WaitStats, WaitSummary};
+
+#[cfg(not(feature = "no_std"))]
+mod spec;
+#[cfg(not(feature = "no_std"))]
+pub use spec::ParsePolicyError;
+
+#[cfg(all(not(feature = "no_std"), any(test, feature = "test-util")))]
+mod test_waiter;
+#[cfg(all(not(feature = "no_std"), any(test, feature = "test-util")))]
+pub use test_waiter::TestWaiter;
 
 #[cfg(not(feature = "no_std"))]
 mod timeout;
 #[cfg(not(feature = "no_std"))]
-pub use timeout::TimeoutWaiter;
+use timeout::OnProgress;
+#[cfg(not(feature = "no_std"))]
+pub use timeout::{DeadlineWaiter, MaxElapsedWaiter, Progress, TimeoutWaiter};
+
+#[cfg(all(feature = "tracing", not(feature = "no_std")))]
+mod trace;
+
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+mod wakers;
+
+#[cfg(not(feature = "no_std"))]
+mod until;
+#[cfg(not(feature = "no_std"))]
+pub use until::SystemTimeWaiter;
+
+mod retry;
+#[cfg(feature = "async")]
+pub use retry::{hedge, retry_async, retry_async_if, retry_async_with_result};
+pub use retry::{retry, retry_if, retry_with_result, AttemptError, RetryDecision, RetryError};
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub use retry::{
+    retry_async_with_attempt_timeout, retry_async_with_breaker, retry_async_with_budget,
+    retry_async_with_overall_timeout,
+};
+#[cfg(not(feature = "no_std"))]
+pub use retry::{retry_with_breaker, retry_with_budget, retry_with_overall_timeout};
+
+/// The `#[retry]` attribute. It is not at the crate root, where `retry` is the [retry!]
+/// macro, so use it as `#[garcon::macros::retry(...)]`.
+#[cfg(all(feature = "macros", not(feature = "no_std")))]
+pub mod macros {
+    pub use garcon_macros::retry;
+}
+#[cfg(all(test, feature = "macros"))]
+extern crate self as garcon;
 
 mod compose;
-pub use compose::DelayComposer;
+pub use compose::{DelayComposer, MaxAttemptsWaiter, SequenceWaiter, WaiterExt};
+#[cfg(not(feature = "no_std"))]
+pub use compose::{MaxOf, MinOf};
+
+// The dev-dependency is renamed so it doesn't collide with the `tokio` feature, but
+// `#[tokio::test]` expands to paths under `::tokio`.
+#[cfg(test)]
+extern crate tokio_crate as tokio;
 
 #[cfg(test)]
 mod tests;
@@ -31,13 +293,65 @@ mod tests;
 /// An error happened while waiting.
 #[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum WaiterError {
-    Timeout,
+    /// The waiter gave up, after waiting a number of times for a total time. These are as
+    /// far as the waiters keep track, see [Waiter::attempts] and [Waiter::elapsed].
+    Timeout {
+        elapsed: Duration,
+        attempts: usize,
+    },
     NotStarted,
+    Cancelled,
+}
+
+impl WaiterError {
+    /// Fill a timeout with the progress known by an outer waiter, keeping the most.
+    fn with_progress(self, elapsed: Duration, attempts: usize) -> Self {
+        match self {
+            WaiterError::Timeout {
+                elapsed: e,
+                attempts: a,
+            } => WaiterError::Timeout {
+                elapsed: e.max(elapsed),
+                attempts: a.max(attempts),
+            },
+            other => other,
+        }
+    }
+}
+
+impl core::fmt::Display for WaiterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WaiterError::Timeout { elapsed, attempts } => write!(
+                f,
+                "the waiter timed out after {:?} and {} waits",
+                elapsed, attempts
+            ),
+            WaiterError::NotStarted => f.write_str("the waiter was not started"),
+            WaiterError::Cancelled => f.write_str("the wait was cancelled"),
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for WaiterError {}
+
+/// The progress of a waiter, to continue it later (e.g. after a restart of the process) with
+/// [Waiter::resume]. With the `serde` feature it can be persisted.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WaiterState {
+    pub attempts: usize,
+    pub elapsed: Duration,
 }
 
 /// A waiter trait, that can be used for executing a delay. Waiters need to be
 /// multi-threaded and cloneable.
 /// A waiter should not be reused twice.
+///
+/// Waits take `&mut self`, so waiters keep their state without interior mutability and can't
+/// be used by two threads at once. Each thread should use its own clone; the waiters meant
+/// to coordinate threads (e.g. [TokenBucketWaiter]) share their state between clones.
 pub trait Waiter: WaiterClone + Send + Sync {
     /// Restart the wait timer.
     /// Call after starting the waiter otherwise returns an error.
@@ -52,6 +366,68 @@ pub trait Waiter: WaiterClone + Send + Sync {
     /// Call after starting the waiter otherwise returns an error.
     fn wait(&mut self) -> Result<(), WaiterError>;
 
+    /// Advance the waiter by one cycle like [wait], but return the time it would have
+    /// slept instead of sleeping. By default this calls [wait] and returns a zero duration,
+    /// which is correct for waiters that don't sleep. Waiters that sleep should implement
+    /// this so they can be wrapped (e.g. by [JitterWaiter]).
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.wait().map(|_| Duration::from_secs(0))
+    }
+
+    /// Replace the clock used to measure time and sleep. Waiters that wrap other waiters
+    /// should forward it to them.
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, _clock: Arc<dyn Clock>) {}
+
+    /// The number of successful waits since the waiter was (re)started, if it keeps track.
+    fn attempts(&self) -> usize {
+        0
+    }
+
+    /// The time since the waiter was (re)started, if it keeps track.
+    fn elapsed(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    /// The delay of the next wait, without advancing the waiter. Returns None if the waiter
+    /// doesn't know it (e.g. it is random or not started).
+    fn next_delay(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The time left before the waiter gives up, for waiters with a time limit, e.g. to bound
+    /// the timeout of the next attempt. Returns None if the waiter has no time limit.
+    fn remaining(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The progress of this waiter, see [Waiter::resume].
+    fn snapshot(&self) -> WaiterState {
+        WaiterState {
+            attempts: self.attempts(),
+            elapsed: self.elapsed(),
+        }
+    }
+
+    /// Start the waiter where a [Waiter::snapshot] was taken. By default this starts the
+    /// waiter and advances it without sleeping as many times as the snapshot attempts, so
+    /// waiters that sleep in [wait] without implementing [advance] need to override it. The
+    /// waiters measuring time move their start back by the elapsed time instead, and the
+    /// rate limiters only start, as their state is not in the snapshot.
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        for _ in 0..state.attempts {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    /// Describe the policy of this waiter, e.g. `exponential(500ms x2 ≤ 30s)`. The waiters
+    /// of this crate implement Display and Debug with it.
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("custom")
+    }
+
     /// Async version of [wait]. By default call the blocking wait. Should be implemented
     /// to be non-blocking.
     #[cfg(feature = "async")]
@@ -100,6 +476,38 @@ impl Waiter for Box<dyn Waiter> {
         self.as_mut().wait()
     }
 
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.as_mut().advance()
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.as_mut().set_clock(clock)
+    }
+
+    fn attempts(&self) -> usize {
+        self.as_ref().attempts()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.as_ref().elapsed()
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        self.as_ref().next_delay()
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.as_ref().remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.as_mut().resume(state)
+    }
+
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.as_ref().describe(f)
+    }
+
     /// Async version of [wait]. By default call the blocking wait. Should be implemented
     /// to be non-blocking.
     #[cfg(feature = "async")]
@@ -126,7 +534,12 @@ impl Delay {
 
     /// A Delay that never waits. This can hog resources, so careful.
     pub fn instant() -> Self {
-        Self::from(Box::new(InstantWaiter {}))
+        Self::from(Box::new(InstantWaiter))
+    }
+
+    /// A Delay that gives up right away, so the operation is not retried.
+    pub fn fail_fast() -> Self {
+        Self::from(Box::new(FailFastWaiter::new()))
     }
 
     /// A Delay that doesn't wait, but times out after a while.
@@ -135,32 +548,184 @@ impl Delay {
         Self::from(Box::new(TimeoutWaiter::new(timeout)))
     }
 
+    /// A Delay that doesn't wait, but times out once the deadline has passed.
+    #[cfg(not(feature = "no_std"))]
+    pub fn deadline(deadline: Instant) -> Self {
+        Self::from(Box::new(DeadlineWaiter::new(deadline)))
+    }
+
+    /// A Delay that waits until a wall clock time, then doesn't wait anymore.
+    #[cfg(not(feature = "no_std"))]
+    pub fn until(time: SystemTime) -> Self {
+        Self::from(Box::new(SystemTimeWaiter::new(time)))
+    }
+
     /// A Delay that times out after waiting a certain number of times.
     pub fn count_timeout(count: u64) -> Self {
         Self::from(Box::new(CountTimeoutWaiter::new(count)))
     }
 
     /// A delay that waits every wait() call for a certain time.
-    #[cfg(not(feature = "no_std"))]
     pub fn throttle(throttle: Duration) -> Self {
         Self::from(Box::new(ThrottleWaiter::new(throttle)))
     }
 
+    /// A throttle letting up to `burst` waits return right away after a quiet period, see
+    /// [ThrottleWaiter::with_burst].
+    #[cfg(not(feature = "no_std"))]
+    pub fn throttle_with_burst(throttle: Duration, burst: u32) -> Self {
+        Self::from(Box::new(ThrottleWaiter::new(throttle).with_burst(burst)))
+    }
+
+    /// A delay allowing `ops_per_second` waits per second, see [ThrottleWaiter::from_rate].
+    pub fn throttle_rate(ops_per_second: f64) -> Self {
+        Self::from(Box::new(ThrottleWaiter::from_rate(ops_per_second)))
+    }
+
+    /// A delay allowing `ops` waits per minute.
+    pub fn throttle_per_minute(ops: f64) -> Self {
+        Self::from(Box::new(ThrottleWaiter::per_minute(ops)))
+    }
+
+    /// A delay allowing `ops` waits per hour.
+    pub fn throttle_per_hour(ops: f64) -> Self {
+        Self::from(Box::new(ThrottleWaiter::per_hour(ops)))
+    }
+
     /// A delay that recalculate a wait time every wait() calls and exponentially waits.
     /// The calculation is new_wait_time = max(current_wait_time * multiplier, cap).
-    #[cfg(not(feature = "no_std"))]
     pub fn exponential_backoff_capped(initial: Duration, multiplier: f32, cap: Duration) -> Self {
         Self::from(Box::new(ExponentialBackoffWaiter::new(
             initial, multiplier, cap,
         )))
     }
 
+    /// Like [Delay::exponential_backoff_capped], with a multiplier of
+    /// `numerator / denominator` using only integer arithmetic, so the delays are
+    /// reproducible across platforms.
+    pub fn exponential_backoff_ratio(
+        initial: Duration,
+        numerator: u64,
+        denominator: u64,
+        cap: Duration,
+    ) -> Self {
+        Self::from(Box::new(ExponentialBackoffWaiter::with_ratio(
+            initial,
+            numerator,
+            denominator,
+            cap,
+        )))
+    }
+
     /// A delay that recalculate a wait time every wait() calls and exponentially waits.
     /// The calculation is new_wait_time = current_wait_time * multiplier.
     /// There is no limit for this backoff.
-    #[cfg(not(feature = "no_std"))]
     pub fn exponential_backoff(initial: Duration, multiplier: f32) -> Self {
-        Self::exponential_backoff_capped(initial, multiplier, Duration::from_secs(std::u64::MAX))
+        Self::exponential_backoff_capped(initial, multiplier, Duration::from_secs(u64::MAX))
+    }
+
+    /// A delay that waits a random time between base and three times the previous wait,
+    /// capped. This spreads retries better than exponential backoff with jitter.
+    #[cfg(not(feature = "no_std"))]
+    pub fn decorrelated_jitter(base: Duration, cap: Duration) -> Self {
+        Self::from(Box::new(DecorrelatedJitterWaiter::new(base, cap)))
+    }
+
+    /// A delay that waits a uniformly random time between min and max (inclusive) every
+    /// wait() calls.
+    #[cfg(not(feature = "no_std"))]
+    pub fn random(min: Duration, max: Duration) -> Self {
+        Self::from(Box::new(RandomWaiter::new(min, max)))
+    }
+
+    /// A delay that waits following the Fibonacci sequence (initial, initial, 2 * initial,
+    /// 3 * initial, 5 * initial, ...), capped.
+    #[cfg(not(feature = "no_std"))]
+    pub fn fibonacci_backoff(initial: Duration, cap: Duration) -> Self {
+        Self::from(Box::new(FibonacciBackoffWaiter::new(initial, cap)))
+    }
+
+    /// A delay that waits longer by a fixed increment every wait() calls.
+    /// The calculation is new_wait_time = min(current_wait_time + increment, cap).
+    #[cfg(not(feature = "no_std"))]
+    pub fn linear_backoff(initial: Duration, increment: Duration, cap: Duration) -> Self {
+        Self::from(Box::new(LinearBackoffWaiter::new(initial, increment, cap)))
+    }
+
+    /// A delay where the n-th wait() call waits initial * n^exponent.
+    /// The calculation is wait_time = min(initial * n^exponent, cap).
+    #[cfg(not(feature = "no_std"))]
+    pub fn polynomial_backoff(initial: Duration, exponent: f32, cap: Duration) -> Self {
+        Self::from(Box::new(PolynomialBackoffWaiter::new(
+            initial, exponent, cap,
+        )))
+    }
+
+    /// A delay where the n-th wait() call waits initial * (1 + ln(n)), capped.
+    #[cfg(not(feature = "no_std"))]
+    pub fn logarithmic_backoff(initial: Duration, cap: Duration) -> Self {
+        Self::from(Box::new(LogarithmicBackoffWaiter::new(initial, cap)))
+    }
+
+    /// A delay where the n-th wait() call waits initial * (1 + 1/2 + ... + 1/n), capped.
+    #[cfg(not(feature = "no_std"))]
+    pub fn harmonic_backoff(initial: Duration, cap: Duration) -> Self {
+        Self::from(Box::new(HarmonicBackoffWaiter::new(initial, cap)))
+    }
+
+    /// A delay that waits initial, then for what the growth function returns given the
+    /// previous wait and the number of waits so far, capped.
+    #[cfg(not(feature = "no_std"))]
+    pub fn fn_backoff<F>(initial: Duration, grow: F, cap: Duration) -> Self
+    where
+        F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+    {
+        Self::from(Box::new(FnBackoffWaiter::new(initial, grow).with_cap(cap)))
+    }
+
+    /// A delay that starts waiting for initial, and shortens the waits linearly to target
+    /// over the warm-up window.
+    #[cfg(not(feature = "no_std"))]
+    pub fn warmup(initial: Duration, target: Duration, window: Duration) -> Self {
+        Self::from(Box::new(WarmupWaiter::new(initial, target, window)))
+    }
+
+    /// A delay that waits for each duration of the schedule in order, then times out.
+    #[cfg(not(feature = "no_std"))]
+    pub fn fixed_schedule(schedule: Vec<Duration>) -> Self {
+        Self::from(Box::new(FixedScheduleWaiter::new(schedule)))
+    }
+
+    /// A delay that allows `rate` waits per second on average, with bursts of up to
+    /// `burst` waits that don't wait.
+    #[cfg(not(feature = "no_std"))]
+    pub fn token_bucket(rate: f64, burst: u32) -> Self {
+        Self::from(Box::new(TokenBucketWaiter::new(rate, burst)))
+    }
+
+    /// A delay that spaces waits evenly at `rate` waits per second, without bursts.
+    #[cfg(not(feature = "no_std"))]
+    pub fn leaky_bucket(rate: f64) -> Self {
+        Self::from(Box::new(LeakyBucketWaiter::new(rate)))
+    }
+
+    /// Like [Delay::token_bucket], but lock free using the Generic Cell Rate Algorithm.
+    #[cfg(not(feature = "no_std"))]
+    pub fn gcra(rate: f64, burst: u32) -> Self {
+        Self::from(Box::new(GcraWaiter::new(rate, burst)))
+    }
+
+    /// A delay that allows at most `max` waits in any rolling window of time.
+    #[cfg(not(feature = "no_std"))]
+    pub fn sliding_window(max: usize, window: Duration) -> Self {
+        Self::from(Box::new(SlidingWindowWaiter::new(max, window)))
+    }
+
+    /// A delay that returns at a fixed rate, taking the time spent between waits out of the
+    /// next wait.
+    #[cfg(not(feature = "no_std"))]
+    pub fn interval(period: Duration) -> Self {
+        Self::from(Box::new(Interval::new(period)))
     }
 
     /// Call a function every tick, expecting some kind of side effect (e.g. a progress
@@ -173,7 +738,15 @@ impl Delay {
     }
 
     pub fn builder() -> DelayBuilder {
-        DelayBuilder { inner: None }
+        DelayBuilder {
+            inner: None,
+            #[cfg(not(feature = "no_std"))]
+            clock: None,
+            #[cfg(not(feature = "no_std"))]
+            max_elapsed: None,
+            #[cfg(not(feature = "no_std"))]
+            on_progress: None,
+        }
     }
 }
 
@@ -191,17 +764,58 @@ impl Waiter for Delay {
         self.inner.start()
     }
     fn wait(&mut self) -> Result<(), WaiterError> {
-        self.inner.wait()
+        let result = self.inner.wait();
+        result.map_err(|e| e.with_progress(self.elapsed(), self.attempts()))
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let result = self.inner.advance();
+        result.map_err(|e| e.with_progress(self.elapsed(), self.attempts()))
+    }
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock)
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.describe(f)
     }
 
     #[cfg(feature = "async")]
     fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
-        self.inner.async_wait()
+        // Waiters give up before sleeping, so the progress from before the wait is current.
+        let (elapsed, attempts) = (self.elapsed(), self.attempts());
+        let future = self.inner.async_wait();
+        Box::pin(async move {
+            let result = future.await;
+            result.map_err(|e| e.with_progress(elapsed, attempts))
+        })
     }
 }
 
+describe_waiter!(Delay);
+
 pub struct DelayBuilder {
     inner: Option<Delay>,
+    #[cfg(not(feature = "no_std"))]
+    clock: Option<Arc<dyn Clock>>,
+    #[cfg(not(feature = "no_std"))]
+    max_elapsed: Option<Duration>,
+    #[cfg(not(feature = "no_std"))]
+    on_progress: Option<OnProgress>,
 }
 impl DelayBuilder {
     /// Add a delay to the current builder. If a builder implements multiple delays, they
@@ -220,14 +834,32 @@ impl DelayBuilder {
         self.with(Delay::timeout(timeout))
     }
     #[cfg(not(feature = "no_std"))]
+    pub fn deadline(self, deadline: Instant) -> Self {
+        self.with(Delay::deadline(deadline))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn until(self, time: SystemTime) -> Self {
+        self.with(Delay::until(time))
+    }
     pub fn throttle(self, throttle: Duration) -> Self {
         self.with(Delay::throttle(throttle))
     }
     #[cfg(not(feature = "no_std"))]
+    pub fn throttle_with_burst(self, throttle: Duration, burst: u32) -> Self {
+        self.with(Delay::throttle_with_burst(throttle, burst))
+    }
+    pub fn throttle_rate(self, ops_per_second: f64) -> Self {
+        self.with(Delay::throttle_rate(ops_per_second))
+    }
+    pub fn throttle_per_minute(self, ops: f64) -> Self {
+        self.with(Delay::throttle_per_minute(ops))
+    }
+    pub fn throttle_per_hour(self, ops: f64) -> Self {
+        self.with(Delay::throttle_per_hour(ops))
+    }
     pub fn exponential_backoff(self, initial: Duration, multiplier: f32) -> Self {
         self.with(Delay::exponential_backoff(initial, multiplier))
     }
-    #[cfg(not(feature = "no_std"))]
     pub fn exponential_backoff_capped(
         self,
         initial: Duration,
@@ -236,28 +868,206 @@ impl DelayBuilder {
     ) -> Self {
         self.with(Delay::exponential_backoff_capped(initial, multiplier, cap))
     }
+    pub fn exponential_backoff_ratio(
+        self,
+        initial: Duration,
+        numerator: u64,
+        denominator: u64,
+        cap: Duration,
+    ) -> Self {
+        self.with(Delay::exponential_backoff_ratio(
+            initial,
+            numerator,
+            denominator,
+            cap,
+        ))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn decorrelated_jitter(self, base: Duration, cap: Duration) -> Self {
+        self.with(Delay::decorrelated_jitter(base, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn random(self, min: Duration, max: Duration) -> Self {
+        self.with(Delay::random(min, max))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn fibonacci_backoff(self, initial: Duration, cap: Duration) -> Self {
+        self.with(Delay::fibonacci_backoff(initial, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn linear_backoff(self, initial: Duration, increment: Duration, cap: Duration) -> Self {
+        self.with(Delay::linear_backoff(initial, increment, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn polynomial_backoff(self, initial: Duration, exponent: f32, cap: Duration) -> Self {
+        self.with(Delay::polynomial_backoff(initial, exponent, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn logarithmic_backoff(self, initial: Duration, cap: Duration) -> Self {
+        self.with(Delay::logarithmic_backoff(initial, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn harmonic_backoff(self, initial: Duration, cap: Duration) -> Self {
+        self.with(Delay::harmonic_backoff(initial, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn fn_backoff<F>(self, initial: Duration, grow: F, cap: Duration) -> Self
+    where
+        F: 'static + FnMut(Duration, usize) -> Duration + Clone + Send + Sync,
+    {
+        self.with(Delay::fn_backoff(initial, grow, cap))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn warmup(self, initial: Duration, target: Duration, window: Duration) -> Self {
+        self.with(Delay::warmup(initial, target, window))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn fixed_schedule(self, schedule: Vec<Duration>) -> Self {
+        self.with(Delay::fixed_schedule(schedule))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn token_bucket(self, rate: f64, burst: u32) -> Self {
+        self.with(Delay::token_bucket(rate, burst))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn leaky_bucket(self, rate: f64) -> Self {
+        self.with(Delay::leaky_bucket(rate))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn gcra(self, rate: f64, burst: u32) -> Self {
+        self.with(Delay::gcra(rate, burst))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn sliding_window(self, max: usize, window: Duration) -> Self {
+        self.with(Delay::sliding_window(max, window))
+    }
+    #[cfg(not(feature = "no_std"))]
+    pub fn interval(self, period: Duration) -> Self {
+        self.with(Delay::interval(period))
+    }
     pub fn side_effect<F>(self, function: F) -> Self
     where
         F: 'static + Sync + Send + Clone + Fn() -> Result<(), WaiterError>,
     {
         self.with(Delay::side_effect(function))
     }
+    /// Randomize the delays of every waiter added so far. Waiters added after this call
+    /// are not affected.
+    #[cfg(not(feature = "no_std"))]
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        let inner = self.inner.take().unwrap_or_else(Delay::instant);
+        self.inner = Some(Delay::from(Box::new(JitterWaiter::new(inner, jitter))));
+        self
+    }
+    /// Time out once the waiters added so far have waited a number of times.
+    pub fn max_attempts(mut self, max_attempts: u64) -> Self {
+        let inner = self.inner.take().unwrap_or_else(Delay::instant);
+        self.inner = Some(Delay::from(Box::new(MaxAttemptsWaiter::new(
+            inner,
+            max_attempts,
+        ))));
+        self
+    }
+    /// Time out once the waiter has been started for a duration. Unlike [DelayBuilder::timeout],
+    /// this applies to the whole waiter wherever it is in the chain, and is checked before
+    /// waiting rather than after.
+    #[cfg(not(feature = "no_std"))]
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+    /// Call a function before each wait with the progress in the budget of
+    /// [DelayBuilder::max_elapsed]. It is never called without a budget.
+    #[cfg(not(feature = "no_std"))]
+    pub fn on_progress<F: Fn(&Progress) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_progress = Some(Arc::new(f));
+        self
+    }
+    /// Use a clock for all the waiters of this builder, instead of the system clock.
+    #[cfg(not(feature = "no_std"))]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
     pub fn build(mut self) -> Delay {
-        self.inner.take().unwrap_or_else(Delay::instant)
+        #[cfg_attr(feature = "no_std", allow(unused_mut))]
+        let mut delay = self.inner.take().unwrap_or_else(Delay::instant);
+        #[cfg(not(feature = "no_std"))]
+        if let Some(max_elapsed) = self.max_elapsed.take() {
+            let mut waiter = MaxElapsedWaiter::new(delay, max_elapsed);
+            if let Some(f) = self.on_progress.take() {
+                waiter = waiter.with_progress(f);
+            }
+            delay = Delay::from(Box::new(waiter));
+        }
+        #[cfg(not(feature = "no_std"))]
+        if let Some(clock) = self.clock.take() {
+            delay.set_clock(clock);
+        }
+        delay
     }
 }
 
-#[derive(Clone)]
-struct InstantWaiter {}
+/// A waiter that never waits and never gives up, e.g. to disable the waits between retries
+/// in a configuration, see [Delay::instant].
+#[derive(Clone, Default)]
+pub struct InstantWaiter;
 impl Waiter for InstantWaiter {
     fn wait(&mut self) -> Result<(), WaiterError> {
         Ok(())
     }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(Duration::from_secs(0))
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("instant")
+    }
 }
 
+describe_waiter!(InstantWaiter);
+
+/// A waiter that refuses to wait, e.g. to fail fast instead of retrying in a configuration,
+/// see [Delay::fail_fast]. Its waits return a timeout after no attempts, or the error given
+/// to [FailFastWaiter::with_error].
+#[derive(Clone)]
+pub struct FailFastWaiter {
+    error: WaiterError,
+}
+impl FailFastWaiter {
+    pub fn new() -> Self {
+        Self::with_error(WaiterError::Timeout {
+            elapsed: Duration::from_secs(0),
+            attempts: 0,
+        })
+    }
+
+    pub fn with_error(error: WaiterError) -> Self {
+        Self { error }
+    }
+}
+impl Default for FailFastWaiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Waiter for FailFastWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        Err(self.error)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        Err(self.error)
+    }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("fail_fast")
+    }
+}
+
+describe_waiter!(FailFastWaiter);
+
+#[derive(Clone)]
 struct CountTimeoutWaiter {
     max_count: u64,
-    count: Option<AtomicU64>,
+    count: Option<u64>,
 }
 impl CountTimeoutWaiter {
     pub fn new(max_count: u64) -> Self {
@@ -267,41 +1077,47 @@ impl CountTimeoutWaiter {
         }
     }
 }
-impl Clone for CountTimeoutWaiter {
-    fn clone(&self) -> Self {
-        Self {
-            max_count: self.max_count,
-            count: self
-                .count
-                .as_ref()
-                .map(|count| AtomicU64::new(count.load(Ordering::Relaxed))),
-        }
-    }
-}
 impl Waiter for CountTimeoutWaiter {
     fn restart(&mut self) -> Result<(), WaiterError> {
         if self.count.is_none() {
             Err(WaiterError::NotStarted)
         } else {
-            self.count = Some(AtomicU64::new(0));
+            self.count = Some(0);
             Ok(())
         }
     }
 
     fn start(&mut self) {
-        self.count = Some(AtomicU64::new(0));
+        self.count = Some(0);
     }
 
     fn wait(&mut self) -> Result<(), WaiterError> {
         let count = self.count.as_mut().ok_or(WaiterError::NotStarted)?;
 
-        let current = count.fetch_add(1, Ordering::Relaxed);
+        let current = *count;
+        *count = count.saturating_add(1);
         if current >= self.max_count {
-            Err(WaiterError::Timeout)
+            Err(WaiterError::Timeout {
+                elapsed: Duration::from_secs(0),
+                attempts: self.max_count as usize,
+            })
         } else {
             Ok(())
         }
     }
+
+    fn attempts(&self) -> usize {
+        self.count
+            .map_or(0, |count| count.min(self.max_count) as usize)
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        Some(Duration::from_secs(0))
+    }
+
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "count_timeout({})", self.max_count)
+    }
 }
 
 #[derive(Clone)]
@@ -327,4 +1143,7 @@ where
     fn wait(&mut self) -> Result<(), WaiterError> {
         (self.function)()
     }
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("side_effect")
+    }
 }
@@ -0,0 +1,93 @@
+#![cfg(all(feature = "metrics", not(feature = "no_std")))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A waiter that records metrics about its inner waiter through the `metrics` facade, all
+/// labelled with `policy`:
+/// - `delay_waits_total`, a counter of the waits performed;
+/// - `delay_wait_seconds`, a histogram of the time waited;
+/// - `delay_timeouts_total`, a counter of the times the waiter gave up with
+///   [WaiterError::Timeout].
+#[derive(Clone)]
+pub struct MeteredWaiter<W: Waiter> {
+    inner: W,
+    policy: Cow<'static, str>,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> MeteredWaiter<W> {
+    pub fn new<S: Into<Cow<'static, str>>>(inner: W, policy: S) -> Self {
+        Self {
+            inner,
+            policy: policy.into(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn record(&self, delay: &Result<Duration, WaiterError>) {
+        match delay {
+            Ok(delay) => {
+                let seconds = delay.as_secs_f64();
+                metrics::counter!("delay_waits_total", 1, "policy" => self.policy.clone());
+                metrics::histogram!("delay_wait_seconds", seconds, "policy" => self.policy.clone());
+            }
+            Err(WaiterError::Timeout { .. }) => {
+                metrics::counter!("delay_timeouts_total", 1, "policy" => self.policy.clone());
+            }
+            Err(_) => {}
+        }
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for MeteredWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let delay = self.advance();
+        sleep(delay, self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.inner.advance();
+        self.record(&delay);
+        delay
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(MeteredWaiter<W>);
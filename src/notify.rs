@@ -0,0 +1,222 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock, WakeHandle};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// How a wait of a [NotifiableWaiter] ended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Wakeup {
+    /// The whole delay elapsed.
+    Elapsed,
+    /// The [Notifier] woke the wait before the end of the delay.
+    Notified,
+}
+
+#[derive(Default)]
+struct NotifyState {
+    /// Incremented by every notification, so each waiter can tell whether it missed one.
+    generation: Mutex<u64>,
+    wake: WakeHandle,
+    /// The wakers of the pending async waits.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A handle to wake the waits of [NotifiableWaiter]s before the end of their delay, e.g.
+/// when new work arrives. Clones wake the same waiters.
+#[derive(Clone, Default)]
+pub struct Notifier {
+    state: Arc<NotifyState>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wake all the waits in progress. A waiter that is not waiting returns right away from
+    /// its next wait instead, so a notification sent just before a wait isn't lost.
+    pub fn notify(&self) {
+        {
+            let mut generation = self.state.generation.lock().unwrap();
+            *generation = generation.wrapping_add(1);
+        }
+        #[cfg(feature = "async")]
+        self.state.wakers.wake_all();
+        self.state.wake.wake();
+    }
+
+    fn generation(&self) -> u64 {
+        *self.state.generation.lock().unwrap()
+    }
+
+    /// Sleep with the clock until the duration elapsed, or until a notification newer than
+    /// `seen`. Returns the generation at the end of the wait.
+    fn wait(&self, clock: &dyn Clock, duration: Duration, seen: u64) -> (Wakeup, u64) {
+        clock.sleep_interruptible(duration, &self.state.wake, &|| self.generation() != seen);
+        // A notification right as the delay elapsed still counts, so it isn't lost.
+        let generation = self.generation();
+        let wakeup = if generation != seen {
+            Wakeup::Notified
+        } else {
+            Wakeup::Elapsed
+        };
+        (wakeup, generation)
+    }
+}
+
+/// A future that resolves on a notification newer than the generation last seen by its
+/// waiter, updating it.
+#[cfg(feature = "async")]
+struct NotifiedFuture {
+    notifier: Notifier,
+    seen: Arc<AtomicU64>,
+    slot: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl Future for NotifiedFuture {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = self.notifier.state.clone();
+        let generation = state.generation.lock().unwrap();
+        if *generation != self.seen.load(Ordering::SeqCst) {
+            self.seen.store(*generation, Ordering::SeqCst);
+            return Poll::Ready(());
+        }
+        self.slot.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// A waiter whose waits end early when its [Notifier] is notified. Blocking waits sleep with
+/// the clock of this waiter, which [Waiter::set_clock] also sets. Use
+/// [NotifiableWaiter::wait_notified] to tell an early wake up from a full wait.
+pub struct NotifiableWaiter<W: Waiter> {
+    inner: W,
+    notifier: Notifier,
+    /// The generation of the last notification this waiter was woken by, shared with its
+    /// async waits.
+    seen: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter + Clone> Clone for NotifiableWaiter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            notifier: self.notifier.clone(),
+            seen: Arc::new(AtomicU64::new(self.seen.load(Ordering::SeqCst))),
+            clock: self.clock.clone(),
+        }
+    }
+}
+impl<W: Waiter> NotifiableWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_notifier(inner, Notifier::new())
+    }
+
+    /// Create a waiter woken by an existing notifier.
+    pub fn with_notifier(inner: W, notifier: Notifier) -> Self {
+        let seen = Arc::new(AtomicU64::new(notifier.generation()));
+        Self {
+            inner,
+            notifier,
+            seen,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn notifier(&self) -> Notifier {
+        self.notifier.clone()
+    }
+
+    /// Ignore the notifications sent so far.
+    fn mark_seen(&self) {
+        self.seen
+            .store(self.notifier.generation(), Ordering::SeqCst);
+    }
+
+    /// Wait like [Waiter::wait], returning whether the wait was cut short by a notification.
+    pub fn wait_notified(&mut self) -> Result<Wakeup, WaiterError> {
+        let delay = self.inner.advance();
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::wait_span(&self.inner, &delay);
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+        let seen = self.seen.load(Ordering::SeqCst);
+        let (wakeup, seen) = self.notifier.wait(self.clock.as_ref(), delay?, seen);
+        self.seen.store(seen, Ordering::SeqCst);
+        Ok(wakeup)
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for NotifiableWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.mark_seen();
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.mark_seen();
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.wait_notified().map(|_| ())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.mark_seen();
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        f.write_str(", notifiable")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        use futures_util::future::{select, Either};
+
+        let wait = self.inner.async_wait();
+        let notified = NotifiedFuture {
+            notifier: self.notifier.clone(),
+            seen: self.seen.clone(),
+            slot: self.notifier.state.wakers.slot(),
+        };
+        Box::pin(async move {
+            match select(wait, notified).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Ok(()),
+            }
+        })
+    }
+}
+
+describe_waiter!(NotifiableWaiter<W>);
@@ -0,0 +1,124 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+type BeforeWait = Arc<dyn Fn(Duration) + Send + Sync>;
+type AfterWait = Arc<dyn Fn(&Result<(), WaiterError>) + Send + Sync>;
+type OnRestart = Arc<dyn Fn() + Send + Sync>;
+
+/// A waiter that calls user callbacks around the waits of its inner waiter, e.g. to
+/// log or count retries without changing the waiter itself.
+#[derive(Clone)]
+pub struct ObservedWaiter<W: Waiter> {
+    inner: W,
+    before_wait: Option<BeforeWait>,
+    after_wait: Option<AfterWait>,
+    on_restart: Option<OnRestart>,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> ObservedWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            before_wait: None,
+            after_wait: None,
+            on_restart: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Called before each wait with the duration about to be waited.
+    pub fn before_wait<F: Fn(Duration) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.before_wait = Some(Arc::new(f));
+        self
+    }
+
+    /// Called after each wait with its result, including when the waiter gave up.
+    pub fn after_wait<F: Fn(&Result<(), WaiterError>) + Send + Sync + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.after_wait = Some(Arc::new(f));
+        self
+    }
+
+    /// Called each time the waiter is restarted.
+    pub fn on_restart<F: Fn() + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_restart = Some(Arc::new(f));
+        self
+    }
+
+    fn observe_advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.inner.advance();
+        if let (Ok(delay), Some(f)) = (&delay, &self.before_wait) {
+            f(*delay);
+        }
+        delay
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for ObservedWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()?;
+        if let Some(f) = &self.on_restart {
+            f();
+        }
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let result = sleep(self.observe_advance(), self.clock.as_ref(), self);
+        if let Some(f) = &self.after_wait {
+            f(&result);
+        }
+        result
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        let future = async_sleep(self.observe_advance(), self.clock.as_ref(), self);
+        let after_wait = self.after_wait.clone();
+        Box::pin(async move {
+            let result = future.await;
+            if let Some(f) = after_wait {
+                f(&result);
+            }
+            result
+        })
+    }
+}
+
+describe_waiter!(ObservedWaiter<W>);
@@ -0,0 +1,179 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock, WakeHandle};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// State shared between a [PauseHandle] and its waiters.
+#[derive(Default)]
+struct PauseState {
+    paused: Mutex<bool>,
+    wake: WakeHandle,
+    /// The wakers of the async waits blocked by the pause.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A handle to pause waits, e.g. to stop a retry loop from hammering a dependency during an
+/// incident. Waits already sleeping complete, but the next waits of the waiters using this
+/// handle block until it is resumed.
+#[derive(Clone, Default)]
+pub struct PauseHandle {
+    state: Arc<PauseState>,
+}
+impl PauseHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pause(&self) {
+        *self.state.paused.lock().unwrap() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.state.paused.lock().unwrap() = false;
+        #[cfg(feature = "async")]
+        self.state.wakers.wake_all();
+        self.state.wake.wake();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.state.paused.lock().unwrap()
+    }
+
+    /// Sleep with the clock until resumed.
+    fn block(&self, clock: &dyn Clock) {
+        while self.is_paused() {
+            clock.sleep_interruptible(Duration::from_secs(3600), &self.state.wake, &|| {
+                !self.is_paused()
+            });
+        }
+    }
+}
+
+/// A future that resolves when the handle is not paused.
+#[cfg(feature = "async")]
+struct ResumedFuture {
+    handle: PauseHandle,
+    slot: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl ResumedFuture {
+    fn new(handle: PauseHandle) -> Self {
+        let slot = handle.state.wakers.slot();
+        Self { handle, slot }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for ResumedFuture {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = self.handle.state.clone();
+        let paused = state.paused.lock().unwrap();
+        if !*paused {
+            return Poll::Ready(());
+        }
+        self.slot.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// A waiter whose waits can be paused with a [PauseHandle]. A paused wait blocks until
+/// resumed, then waits for the delay of its inner waiter as usual. The time spent paused
+/// doesn't count in the delay, but does in the elapsed time of the inner waiter.
+///
+/// Only waits through this waiter are paused, not [Waiter::advance] which must not block an
+/// executor, so it should be the outermost of the waiters wrapping each other.
+#[derive(Clone)]
+pub struct PausableWaiter<W: Waiter> {
+    inner: W,
+    handle: PauseHandle,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> PausableWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_handle(inner, PauseHandle::new())
+    }
+
+    /// Create a waiter paused by an existing handle.
+    pub fn with_handle(inner: W, handle: PauseHandle) -> Self {
+        Self {
+            inner,
+            handle,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn handle(&self) -> PauseHandle {
+        self.handle.clone()
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for PausableWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.handle.block(self.clock.as_ref());
+        self.inner.wait()
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        f.write_str(", pausable")
+    }
+
+    /// The delay is taken from the inner waiter right away, and slept once resumed.
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        if !self.handle.is_paused() {
+            return self.inner.async_wait();
+        }
+        let delay = self.inner.advance();
+        let resumed = ResumedFuture::new(self.handle.clone());
+        let clock = self.clock.clone();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            resumed.await;
+            async_sleep(delay, clock.as_ref(), &inner).await
+        })
+    }
+}
+
+describe_waiter!(PausableWaiter<W>);
@@ -0,0 +1,106 @@
+#![cfg(not(feature = "no_std"))]
+use crate::{Delay, Jitter};
+use std::time::Duration;
+
+/// A description of a delay, which can be built into a [Delay]. With the `serde` feature,
+/// this can be read from configuration files.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DelayPolicy {
+    Instant,
+    Timeout(Duration),
+    CountTimeout(u64),
+    Throttle(Duration),
+    ExponentialBackoff {
+        initial: Duration,
+        multiplier: f32,
+        #[cfg_attr(feature = "serde", serde(default))]
+        cap: Option<Duration>,
+    },
+    DecorrelatedJitter {
+        base: Duration,
+        cap: Duration,
+    },
+    FibonacciBackoff {
+        initial: Duration,
+        cap: Duration,
+    },
+    LinearBackoff {
+        initial: Duration,
+        increment: Duration,
+        cap: Duration,
+    },
+    PolynomialBackoff {
+        initial: Duration,
+        exponent: f32,
+        cap: Duration,
+    },
+    FixedSchedule(Vec<Duration>),
+    /// Randomize the delays of the inner policy.
+    Jitter {
+        jitter: Jitter,
+        inner: Box<DelayPolicy>,
+    },
+    /// Time out once the inner policy has waited a number of times.
+    MaxAttempts {
+        max_attempts: u64,
+        inner: Box<DelayPolicy>,
+    },
+    /// Run all the policies one after the other on every wait, like [crate::DelayBuilder].
+    Compose(Vec<DelayPolicy>),
+}
+
+impl DelayPolicy {
+    pub fn build(&self) -> Delay {
+        match self {
+            DelayPolicy::Instant => Delay::instant(),
+            DelayPolicy::Timeout(timeout) => Delay::timeout(*timeout),
+            DelayPolicy::CountTimeout(count) => Delay::count_timeout(*count),
+            DelayPolicy::Throttle(throttle) => Delay::throttle(*throttle),
+            DelayPolicy::ExponentialBackoff {
+                initial,
+                multiplier,
+                cap: Some(cap),
+            } => Delay::exponential_backoff_capped(*initial, *multiplier, *cap),
+            DelayPolicy::ExponentialBackoff {
+                initial,
+                multiplier,
+                cap: None,
+            } => Delay::exponential_backoff(*initial, *multiplier),
+            DelayPolicy::DecorrelatedJitter { base, cap } => {
+                Delay::decorrelated_jitter(*base, *cap)
+            }
+            DelayPolicy::FibonacciBackoff { initial, cap } => {
+                Delay::fibonacci_backoff(*initial, *cap)
+            }
+            DelayPolicy::LinearBackoff {
+                initial,
+                increment,
+                cap,
+            } => Delay::linear_backoff(*initial, *increment, *cap),
+            DelayPolicy::PolynomialBackoff {
+                initial,
+                exponent,
+                cap,
+            } => Delay::polynomial_backoff(*initial, *exponent, *cap),
+            DelayPolicy::FixedSchedule(schedule) => Delay::fixed_schedule(schedule.clone()),
+            DelayPolicy::Jitter { jitter, inner } => {
+                Delay::builder().with(inner.build()).jitter(*jitter).build()
+            }
+            DelayPolicy::MaxAttempts {
+                max_attempts,
+                inner,
+            } => Delay::builder()
+                .with(inner.build())
+                .max_attempts(*max_attempts)
+                .build(),
+            DelayPolicy::Compose(policies) => policies
+                .iter()
+                .fold(Delay::builder(), |builder, policy| {
+                    builder.with(policy.build())
+                })
+                .build(),
+        }
+    }
+}
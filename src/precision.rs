@@ -0,0 +1,105 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A waiter that sleeps for the delays of its inner waiter up to a margin before the
+/// deadline, then spins for the rest. Sleeping routinely overshoots by a few milliseconds,
+/// so this trades some CPU for precise waits, e.g. when pacing hardware.
+///
+/// The async wait also spins for the margin, which blocks the executor thread meanwhile.
+#[derive(Clone)]
+pub struct PrecisionWaiter<W: Waiter> {
+    inner: W,
+    margin: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<W: Waiter> PrecisionWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            margin: Duration::from_millis(2),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// The time to spin at the end of each wait, 2ms by default.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    fn split(&self, delay: Duration) -> (Duration, Duration) {
+        let spin = delay.min(self.margin);
+        (delay - spin, spin)
+    }
+}
+
+impl<W: 'static + Waiter + Clone> Waiter for PrecisionWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let delay = self.inner.advance()?;
+        let deadline = deadline_after(self.clock.now(), delay);
+        let (coarse, _) = self.split(delay);
+        sleep(Ok(coarse), self.clock.as_ref(), self)?;
+        self.clock
+            .spin(deadline.saturating_duration_since(self.clock.now()));
+        Ok(())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        let delay = match self.inner.advance() {
+            Ok(delay) => delay,
+            Err(e) => return Box::pin(futures_util::future::err(e)),
+        };
+        let deadline = deadline_after(self.clock.now(), delay);
+        let (coarse, _) = self.split(delay);
+        let future = async_sleep(Ok(coarse), self.clock.as_ref(), self);
+        let clock = self.clock.clone();
+        Box::pin(async move {
+            future.await?;
+            clock.spin(deadline.saturating_duration_since(clock.now()));
+            Ok(())
+        })
+    }
+}
+
+describe_waiter!(PrecisionWaiter<W>);
@@ -0,0 +1,72 @@
+#![cfg(not(feature = "no_std"))]
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A random number that is good enough to spread delays. The keys of [RandomState] are
+/// randomly seeded per thread, which saves us from depending on a random crate.
+pub(crate) fn next_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+/// A source of random numbers for the randomized waiters, to reproduce their delays in tests
+/// and simulations.
+pub trait Rng: Send {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// A small seeded generator (SplitMix64), giving the same delays for the same seed.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Rng for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The random numbers of a waiter, from the generator it was given or [next_u64] by default.
+/// Clones share the generator.
+#[derive(Clone, Default)]
+pub(crate) struct RngSource(Option<Arc<Mutex<dyn Rng>>>);
+
+impl RngSource {
+    pub(crate) fn new<R: 'static + Rng>(rng: R) -> Self {
+        Self(Some(Arc::new(Mutex::new(rng))))
+    }
+
+    fn next_u64(&self) -> u64 {
+        match &self.0 {
+            Some(rng) => rng.lock().unwrap().next_u64(),
+            None => next_u64(),
+        }
+    }
+
+    /// A random duration between min and max (inclusive).
+    pub(crate) fn between(&self, min: Duration, max: Duration) -> Duration {
+        if max <= min {
+            return min;
+        }
+        let range = u64::try_from((max - min).as_nanos()).unwrap_or(u64::MAX);
+        min + Duration::from_nanos(self.next_u64() % range.saturating_add(1))
+    }
+}
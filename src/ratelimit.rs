@@ -0,0 +1,425 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// The rate limiters take a number of waits per second, which must be positive: there is
+/// no interval for a rate of zero, and NaN would poison their state.
+fn check_rate(rate: f64) {
+    assert!(rate > 0.0, "the rate {} is not positive", rate);
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Option<Instant>,
+}
+
+/// A rate limiter allowing `rate` waits per second on average, and bursts of up to `burst`
+/// waits without waiting. Each wait takes a token from the bucket, which refills at `rate`
+/// tokens per second, and only waits until its token is available.
+///
+/// Clones share the same bucket, so a clone can be given to each thread or task to limit
+/// all of them together.
+#[derive(Clone)]
+pub struct TokenBucketWaiter {
+    rate: f64,
+    burst: u32,
+    bucket: Arc<Mutex<Bucket>>,
+    clock: Arc<dyn Clock>,
+}
+impl TokenBucketWaiter {
+    /// Panics if the rate is not positive.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        check_rate(rate);
+        Self {
+            rate,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst as f64,
+                last: None,
+            })),
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for TokenBucketWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    /// Take a token, which may not be available yet, and return how long until it is.
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self.clock.now();
+        let mut bucket = self.bucket.lock().unwrap();
+        let refill = bucket.last.map_or(0.0, |last| {
+            now.saturating_duration_since(last).as_secs_f64() * self.rate
+        });
+        bucket.tokens = f64::min(bucket.tokens + refill, self.burst as f64) - 1.0;
+        bucket.last = Some(now);
+
+        if bucket.tokens >= 0.0 {
+            Ok(Duration::from_secs(0))
+        } else {
+            Ok(Duration::from_secs_f64(-bucket.tokens / self.rate))
+        }
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "token_bucket({}/s, burst {})", self.rate, self.burst)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(TokenBucketWaiter);
+
+/// A rate limiter that spaces waits evenly at `rate` waits per second, without bursts. Unlike
+/// a [crate::ThrottleWaiter], time spent between waits counts towards the spacing, so calls
+/// that are already slow enough don't wait at all.
+///
+/// Clones share the same bucket, so a clone can be given to each thread or task to limit
+/// all of them together.
+#[derive(Clone)]
+pub struct LeakyBucketWaiter {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+    clock: Arc<dyn Clock>,
+}
+impl LeakyBucketWaiter {
+    /// Panics if the rate is not positive.
+    pub fn new(rate: f64) -> Self {
+        check_rate(rate);
+        Self {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            next: Arc::new(Mutex::new(None)),
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for LeakyBucketWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    /// Reserve the next slot, and return how long until it.
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self.clock.now();
+        let mut next = self.next.lock().unwrap();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        Ok(slot - now)
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "leaky_bucket(every {:?})", self.interval)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(LeakyBucketWaiter);
+
+struct GcraState {
+    base: Instant,
+    /// The theoretical arrival time of the next wait, in nanoseconds since `base`.
+    tat: AtomicU64,
+}
+impl GcraState {
+    fn new(base: Instant) -> Arc<Self> {
+        Arc::new(Self {
+            base,
+            tat: AtomicU64::new(0),
+        })
+    }
+}
+
+/// A rate limiter with the same behaviour as a [TokenBucketWaiter], using the Generic Cell
+/// Rate Algorithm. Its state is a single atomic, so it doesn't lock even when many threads
+/// share it.
+///
+/// Clones share the same state, so a clone can be given to each thread or task to limit
+/// all of them together. Setting the clock resets the state.
+#[derive(Clone)]
+pub struct GcraWaiter {
+    interval: u64,
+    tolerance: u64,
+    state: Arc<GcraState>,
+    clock: Arc<dyn Clock>,
+}
+impl GcraWaiter {
+    /// Panics if the rate is not positive.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        check_rate(rate);
+        let interval = (1e9 / rate) as u64;
+        let clock = SystemClock::shared();
+        Self {
+            interval,
+            tolerance: interval * u64::from(burst.max(1) - 1),
+            state: GcraState::new(clock.now()),
+            clock,
+        }
+    }
+}
+impl Waiter for GcraWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    /// Reserve the next arrival time, and return how long until it conforms.
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self
+            .clock
+            .now()
+            .saturating_duration_since(self.state.base)
+            .as_nanos() as u64;
+        let mut tat = self.state.tat.load(Ordering::Relaxed);
+        loop {
+            let arrival = tat.max(now);
+            match self.state.tat.compare_exchange_weak(
+                tat,
+                arrival + self.interval,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    let allowed = arrival.saturating_sub(self.tolerance);
+                    return Ok(Duration::from_nanos(allowed.saturating_sub(now)));
+                }
+                Err(current) => tat = current,
+            }
+        }
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.state = GcraState::new(clock.now());
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "gcra(every {:?}, burst {})",
+            Duration::from_nanos(self.interval),
+            self.tolerance / self.interval.max(1) + 1
+        )
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(GcraWaiter);
+
+/// A rate limiter allowing at most `max` waits in any rolling window of time. It keeps the
+/// time of the last `max` waits, and a wait only returns once the oldest of them is out of
+/// the window.
+///
+/// Clones share the same window, so a clone can be given to each thread or task to limit
+/// all of them together.
+#[derive(Clone)]
+pub struct SlidingWindowWaiter {
+    max: usize,
+    window: Duration,
+    calls: Arc<Mutex<VecDeque<Instant>>>,
+    clock: Arc<dyn Clock>,
+}
+impl SlidingWindowWaiter {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            calls: Arc::new(Mutex::new(VecDeque::with_capacity(max))),
+            clock: SystemClock::shared(),
+        }
+    }
+}
+impl Waiter for SlidingWindowWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    /// Reserve the first time the window has room, and return how long until it.
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self.clock.now();
+        let mut calls = self.calls.lock().unwrap();
+        let slot = if calls.len() < self.max {
+            now
+        } else {
+            let oldest = calls.pop_front().map_or(now, |oldest| oldest + self.window);
+            oldest.max(now)
+        };
+        calls.push_back(slot);
+        Ok(slot - now)
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sliding_window({} per {:?})", self.max, self.window)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(SlidingWindowWaiter);
+
+/// The priority class of a [PriorityWaiter].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Priority::Low => 0,
+            Priority::Normal => 1,
+            Priority::High => 2,
+        }
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Normal
+    }
+}
+
+/// A rate limiter spacing waits at `rate` waits per second overall, sharing the rate between
+/// priority classes by weight. A class alone gets the whole rate; when several classes have
+/// waits queued, each gets a share proportional to its weight (by default 1 for low, 2 for
+/// normal and 4 for high), so high priority waits don't queue behind a backlog of low
+/// priority ones.
+///
+/// Clones share the same limiter, use [PriorityWaiter::for_priority] to get a waiter for
+/// each class.
+#[derive(Clone)]
+pub struct PriorityWaiter {
+    interval: Duration,
+    weights: [u32; 3],
+    priority: Priority,
+    /// The next free slot of each class.
+    next: Arc<Mutex<[Option<Instant>; 3]>>,
+    clock: Arc<dyn Clock>,
+}
+impl PriorityWaiter {
+    /// A limiter waiting with the normal priority. Panics if the rate is not positive.
+    pub fn new(rate: f64) -> Self {
+        check_rate(rate);
+        Self {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            weights: [1, 2, 4],
+            priority: Priority::Normal,
+            next: Arc::new(Mutex::new([None; 3])),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// The share of the rate of each class when they compete. Weights of zero count as one.
+    pub fn with_weights(mut self, low: u32, normal: u32, high: u32) -> Self {
+        self.weights = [low.max(1), normal.max(1), high.max(1)];
+        self
+    }
+
+    /// A waiter of this limiter with another priority.
+    pub fn for_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+impl Waiter for PriorityWaiter {
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    /// Reserve the next slot of this class, spaced by its share of the rate among the
+    /// classes with waits queued, and return how long until it.
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let now = self.clock.now();
+        let class = self.priority.index();
+        let mut next = self.next.lock().unwrap();
+        let active: u32 = (0..3)
+            .filter(|&i| i == class || matches!(next[i], Some(next) if next > now))
+            .map(|i| self.weights[i])
+            .sum();
+        let interval = self
+            .interval
+            .checked_mul(active)
+            .unwrap_or(Duration::from_secs(u64::MAX))
+            / self.weights[class];
+        let slot = next[class].map_or(now, |next| next.max(now));
+        next[class] = Some(deadline_after(slot, interval));
+        Ok(slot - now)
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "priority(every {:?}, {:?})",
+            self.interval, self.priority
+        )
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(PriorityWaiter);
@@ -0,0 +1,176 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+use crate::{Waiter, WaiterError};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+struct Entry {
+    waiter: Box<dyn Waiter>,
+    last_used: Instant,
+}
+
+type Factory<K> = Arc<dyn Fn(&K) -> Box<dyn Waiter> + Send + Sync>;
+
+/// An independent waiter per key (e.g. per endpoint or peer), created and started the first
+/// time the key is used. Keys that weren't used for the idle timeout are forgotten, so their
+/// next use starts a fresh backoff.
+///
+/// Clones share the same waiters.
+pub struct BackoffMap<K> {
+    entries: Arc<Mutex<HashMap<K, Entry>>>,
+    factory: Factory<K>,
+    idle: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K> Clone for BackoffMap<K> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            factory: self.factory.clone(),
+            idle: self.idle,
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> BackoffMap<K> {
+    /// Create the waiter of a key with the factory.
+    pub fn new<W, F>(factory: F) -> Self
+    where
+        W: 'static + Waiter,
+        F: 'static + Fn(&K) -> W + Send + Sync,
+    {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            factory: Arc::new(move |key| Box::new(factory(key))),
+            idle: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Forget the keys that weren't used for this long. They are kept forever by default.
+    pub fn with_idle_timeout(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Use a clock instead of the system clock, for this map and the waiters it creates.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        for entry in self.entries.lock().unwrap().values_mut() {
+            entry.waiter.set_clock(clock.clone());
+        }
+        self.clock = clock;
+        self
+    }
+
+    /// Wait for the waiter of the key.
+    pub fn wait(&self, key: &K) -> Result<(), WaiterError> {
+        // The span describes the waiter while the map is locked, but the sleep is after.
+        #[cfg(feature = "tracing")]
+        let (delay, span) = self.advance(key, |delay, waiter| {
+            let span = crate::trace::wait_span(waiter, &delay);
+            (delay, span)
+        });
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+        #[cfg(not(feature = "tracing"))]
+        let delay = self.advance(key, |delay, _| delay);
+
+        self.clock.sleep(delay?);
+        Ok(())
+    }
+
+    /// Async version of [BackoffMap::wait].
+    #[cfg(feature = "async")]
+    pub fn async_wait(
+        &self,
+        key: &K,
+    ) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        self.advance(key, |delay, waiter| {
+            async_sleep(delay, self.clock.as_ref(), waiter)
+        })
+    }
+
+    /// The delay of the next wait for the key, if its waiter knows it.
+    pub fn next_delay(&self, key: &K) -> Option<Duration> {
+        match self.entries.lock().unwrap().get(key) {
+            Some(entry) => entry.waiter.next_delay(),
+            None => {
+                let mut waiter = (self.factory)(key);
+                waiter.set_clock(self.clock.clone());
+                waiter.start();
+                waiter.next_delay()
+            }
+        }
+    }
+
+    /// Forget the waiter of the key, e.g. after a success, so its next wait starts over.
+    pub fn reset(&self, key: &K) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    /// The number of keys with a waiter.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the keys that were idle for longer than the idle timeout. This also happens
+    /// when a new key is used.
+    pub fn evict_idle(&self) {
+        Self::evict(
+            &mut self.entries.lock().unwrap(),
+            self.idle,
+            self.clock.now(),
+        );
+    }
+
+    fn evict(entries: &mut HashMap<K, Entry>, idle: Option<Duration>, now: Instant) {
+        if let Some(idle) = idle {
+            entries.retain(|_, entry| now.saturating_duration_since(entry.last_used) <= idle);
+        }
+    }
+
+    /// Advance the waiter of the key, and call `f` with its delay while it is locked.
+    fn advance<R>(
+        &self,
+        key: &K,
+        f: impl FnOnce(Result<Duration, WaiterError>, &dyn Waiter) -> R,
+    ) -> R {
+        let now = self.clock.now();
+        let mut entries = self.entries.lock().unwrap();
+        let stale = match (entries.get(key), self.idle) {
+            (None, _) => true,
+            (Some(entry), Some(idle)) => now.saturating_duration_since(entry.last_used) > idle,
+            (Some(_), None) => false,
+        };
+        if stale {
+            Self::evict(&mut entries, self.idle, now);
+            let mut waiter = (self.factory)(key);
+            waiter.set_clock(self.clock.clone());
+            waiter.start();
+            entries.insert(
+                key.clone(),
+                Entry {
+                    waiter,
+                    last_used: now,
+                },
+            );
+        }
+        let entry = entries.get_mut(key).unwrap();
+        entry.last_used = now;
+        let delay = entry.waiter.advance();
+        f(delay, entry.waiter.as_ref())
+    }
+}
@@ -0,0 +1,537 @@
+use crate::Waiter;
+use core::fmt;
+use core::time::Duration;
+
+#[cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+#[cfg(not(feature = "no_std"))]
+use crate::{BudgetError, CircuitBreaker, CircuitError, RetryBudget};
+#[cfg(not(feature = "no_std"))]
+use std::time::Instant;
+
+#[cfg(feature = "async")]
+use core::future::Future;
+
+/// Whether an error returned by an operation should be retried.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RetryDecision {
+    /// The error is transient, wait and try again.
+    Retry,
+    /// The error is permanent, return it right away.
+    Abort,
+}
+
+impl RetryDecision {
+    /// Classify an error, for [retry!] to infer the type the classifier takes.
+    #[doc(hidden)]
+    pub fn of<E, C: FnOnce(&E) -> Self>(error: &E, classify: C) -> Self {
+        classify(error)
+    }
+}
+
+/// The error of [retry_with_result], once the waiter gave up.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RetryError<E> {
+    /// The error of the last attempt.
+    pub source: E,
+    /// The number of times the operation was called.
+    pub attempts: usize,
+    /// The sum of the delays waited between attempts, as reported by [Waiter::next_delay].
+    /// Delays the waiter doesn't know in advance (e.g. random ones) are not counted.
+    pub total_delay: Duration,
+}
+
+impl<E> RetryError<E> {
+    pub fn into_inner(self) -> E {
+        self.source
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (after {} attempts and {:?} of delays)",
+            self.source, self.attempts, self.total_delay
+        )
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl<E: std::error::Error + 'static> std::error::Error for RetryError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Call an operation until it succeeds, waiting between attempts. The waiter is started
+/// before the first attempt, so it can be reused between calls.
+///
+/// Returns the first success, or the last error of the operation once the waiter gives up
+/// (e.g. it timed out).
+pub fn retry<W, T, E, F>(waiter: &mut W, op: F) -> Result<T, E>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+{
+    retry_if(waiter, op, |_| RetryDecision::Retry)
+}
+
+/// Same as [retry], but errors for which the classifier returns [RetryDecision::Abort] are
+/// returned right away without waiting.
+pub fn retry_if<W, T, E, F, C>(waiter: &mut W, mut op: F, mut classify: C) -> Result<T, E>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+    C: FnMut(&E) -> RetryDecision,
+{
+    waiter.start();
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if classify(&e) == RetryDecision::Abort || waiter.wait().is_err() {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Retry an expression returning a `Result`, like [retry()] but without a closure, so the
+/// expression can borrow mutably, `return` or `?` from the enclosing function:
+///
+/// - `retry!(waiter, { expr })` retries every error,
+/// - `retry!(waiter, { expr }, |e| decision)` returns errors classified as
+///   [RetryDecision::Abort] right away, like [retry_if],
+/// - `retry!(async waiter, { expr })` awaits [Waiter::async_wait] between attempts, so the
+///   expression can `.await` inside an async function.
+///
+/// The waiter is a place, e.g. a variable or a field, that is started before the first
+/// attempt. The classifier is evaluated for each error.
+#[macro_export]
+macro_rules! retry {
+    (async $waiter:expr, $body:block) => {
+        $crate::retry!(async $waiter, $body, |_| $crate::RetryDecision::Retry)
+    };
+    (async $waiter:expr, $body:block, $classify:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::Waiter as _;
+        let waiter = &mut $waiter;
+        waiter.start();
+        loop {
+            match $body {
+                Ok(value) => break Ok(value),
+                Err(e) => {
+                    if $crate::RetryDecision::of(&e, $classify) == $crate::RetryDecision::Abort
+                        || waiter.async_wait().await.is_err()
+                    {
+                        break Err(e);
+                    }
+                }
+            }
+        }
+    }};
+    ($waiter:expr, $body:block) => {
+        $crate::retry!($waiter, $body, |_| $crate::RetryDecision::Retry)
+    };
+    ($waiter:expr, $body:block, $classify:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::Waiter as _;
+        let waiter = &mut $waiter;
+        waiter.start();
+        loop {
+            match $body {
+                Ok(value) => break Ok(value),
+                Err(e) => {
+                    if $crate::RetryDecision::of(&e, $classify) == $crate::RetryDecision::Abort || waiter.wait().is_err() {
+                        break Err(e);
+                    }
+                }
+            }
+        }
+    }};
+}
+
+/// Same as [retry], but once the waiter gives up the last error comes with how many
+/// attempts were made and how long they waited, see [RetryError].
+pub fn retry_with_result<W, T, E, F>(waiter: &mut W, mut op: F) -> Result<T, RetryError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+{
+    waiter.start();
+    let mut attempts = 0;
+    let mut total_delay = Duration::from_secs(0);
+    loop {
+        attempts += 1;
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(source) => {
+                let delay = waiter.next_delay();
+                if waiter.wait().is_err() {
+                    return Err(RetryError {
+                        source,
+                        attempts,
+                        total_delay,
+                    });
+                }
+                total_delay += delay.unwrap_or_else(|| Duration::from_secs(0));
+            }
+        }
+    }
+}
+
+/// Same as [retry], but calls go through a circuit breaker. Once the circuit is open, this
+/// returns [CircuitError::Open] right away instead of waiting.
+#[cfg(not(feature = "no_std"))]
+pub fn retry_with_breaker<W, T, E, F>(
+    waiter: &mut W,
+    breaker: &CircuitBreaker,
+    mut op: F,
+) -> Result<T, CircuitError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+{
+    retry_if(waiter, || breaker.call(&mut op), breaker_decision)
+}
+
+/// Same as [retry], but each call deposits into a retry budget shared with other call sites,
+/// and each retry withdraws from it. Once the budget is empty, this returns the last error
+/// as [BudgetError::Exhausted] without waiting.
+#[cfg(not(feature = "no_std"))]
+pub fn retry_with_budget<W, T, E, F>(
+    waiter: &mut W,
+    budget: &RetryBudget,
+    mut op: F,
+) -> Result<T, BudgetError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+{
+    budget.deposit();
+    waiter.start();
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !budget.try_withdraw() {
+                    return Err(BudgetError::Exhausted(e));
+                }
+                if waiter.wait().is_err() {
+                    budget.refund();
+                    return Err(BudgetError::Failed(e));
+                }
+            }
+        }
+    }
+}
+
+/// Same as [retry], but gives up once the overall timeout has passed since the first attempt,
+/// counting the time spent in the attempts as well as in the waits. The loop doesn't wait if
+/// the next wait would end past the timeout, as there would be no time left to try again.
+/// An attempt that is running is not interrupted, see [retry_async_with_overall_timeout].
+/// The timeout is measured with the system clock.
+#[cfg(not(feature = "no_std"))]
+pub fn retry_with_overall_timeout<W, T, E, F>(
+    waiter: &mut W,
+    timeout: Duration,
+    mut op: F,
+) -> Result<T, E>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Result<T, E>,
+{
+    let deadline = Deadline::after(timeout);
+    waiter.start();
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !deadline.allows(waiter) || waiter.wait().is_err() {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// The end of an overall timeout.
+#[cfg(not(feature = "no_std"))]
+struct Deadline(Option<Instant>);
+
+#[cfg(not(feature = "no_std"))]
+impl Deadline {
+    fn after(timeout: Duration) -> Self {
+        Self(SystemClock.now().checked_add(timeout))
+    }
+
+    /// The time left, or none for a timeout too far in the future to be represented.
+    fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|deadline| deadline.saturating_duration_since(SystemClock.now()))
+    }
+
+    /// Whether there is time left to wait for the next delay of the waiter, and try again.
+    fn allows<W: Waiter + ?Sized>(&self, waiter: &W) -> bool {
+        match self.remaining() {
+            Some(remaining) => {
+                let delay = waiter
+                    .next_delay()
+                    .unwrap_or_else(|| Duration::from_secs(0));
+                delay < remaining
+            }
+            None => true,
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+fn breaker_decision<E>(error: &CircuitError<E>) -> RetryDecision {
+    match error {
+        CircuitError::Open => RetryDecision::Abort,
+        CircuitError::Failed(_) => RetryDecision::Retry,
+    }
+}
+
+/// Async version of [retry]. The operation returns a future for each attempt, and the
+/// waiter's [Waiter::async_wait] is awaited between attempts.
+#[cfg(feature = "async")]
+pub async fn retry_async<W, T, E, F, Fut>(waiter: &mut W, op: F) -> Result<T, E>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_async_if(waiter, op, |_| RetryDecision::Retry).await
+}
+
+/// Async version of [retry_if].
+#[cfg(feature = "async")]
+pub async fn retry_async_if<W, T, E, F, Fut, C>(
+    waiter: &mut W,
+    mut op: F,
+    mut classify: C,
+) -> Result<T, E>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    C: FnMut(&E) -> RetryDecision,
+{
+    waiter.start();
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if classify(&e) == RetryDecision::Abort || waiter.async_wait().await.is_err() {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Async version of [retry_with_budget].
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub async fn retry_async_with_budget<W, T, E, F, Fut>(
+    waiter: &mut W,
+    budget: &RetryBudget,
+    mut op: F,
+) -> Result<T, BudgetError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    budget.deposit();
+    waiter.start();
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !budget.try_withdraw() {
+                    return Err(BudgetError::Exhausted(e));
+                }
+                if waiter.async_wait().await.is_err() {
+                    budget.refund();
+                    return Err(BudgetError::Failed(e));
+                }
+            }
+        }
+    }
+}
+
+/// The error of an attempt of [retry_async_with_attempt_timeout].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AttemptError<E> {
+    /// The attempt took longer than the attempt timeout, and was cancelled.
+    TimedOut,
+    /// The attempt failed.
+    Failed(E),
+}
+
+impl<E> AttemptError<E> {
+    /// The error of the attempt, if it didn't time out.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            AttemptError::TimedOut => None,
+            AttemptError::Failed(e) => Some(e),
+        }
+    }
+}
+
+/// Same as [retry_async], but each attempt is cancelled once it runs for longer than the
+/// attempt timeout, and retried like a failure, so a hung attempt doesn't use up the whole
+/// retry loop. The timeout is measured with the system clock.
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub async fn retry_async_with_attempt_timeout<W, T, E, F, Fut>(
+    waiter: &mut W,
+    attempt_timeout: Duration,
+    mut op: F,
+) -> Result<T, AttemptError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    use futures_util::future::{select, Either};
+
+    let op = || {
+        let attempt = Box::pin(op());
+        async move {
+            match select(attempt, SystemClock.async_sleep(attempt_timeout)).await {
+                Either::Left((result, _)) => result.map_err(AttemptError::Failed),
+                Either::Right(_) => Err(AttemptError::TimedOut),
+            }
+        }
+    };
+    retry_async(waiter, op).await
+}
+
+/// Async version of [retry_with_overall_timeout]. An attempt still running at the timeout is
+/// cancelled, and returns [AttemptError::TimedOut].
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub async fn retry_async_with_overall_timeout<W, T, E, F, Fut>(
+    waiter: &mut W,
+    timeout: Duration,
+    mut op: F,
+) -> Result<T, AttemptError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    use futures_util::future::{select, Either};
+
+    let deadline = Deadline::after(timeout);
+    waiter.start();
+    loop {
+        let attempt = Box::pin(op());
+        let result = match deadline.remaining() {
+            Some(remaining) => match select(attempt, SystemClock.async_sleep(remaining)).await {
+                Either::Left((result, _)) => result.map_err(AttemptError::Failed),
+                Either::Right(_) => return Err(AttemptError::TimedOut),
+            },
+            None => attempt.await.map_err(AttemptError::Failed),
+        };
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !deadline.allows(waiter) || waiter.async_wait().await.is_err() {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Async version of [retry_with_result].
+#[cfg(feature = "async")]
+pub async fn retry_async_with_result<W, T, E, F, Fut>(
+    waiter: &mut W,
+    mut op: F,
+) -> Result<T, RetryError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    waiter.start();
+    let mut attempts = 0;
+    let mut total_delay = Duration::from_secs(0);
+    loop {
+        attempts += 1;
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(source) => {
+                let delay = waiter.next_delay();
+                if waiter.async_wait().await.is_err() {
+                    return Err(RetryError {
+                        source,
+                        attempts,
+                        total_delay,
+                    });
+                }
+                total_delay += delay.unwrap_or_else(|| Duration::from_secs(0));
+            }
+        }
+    }
+}
+
+/// Async version of [retry_with_breaker].
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub async fn retry_async_with_breaker<W, T, E, F, Fut>(
+    waiter: &mut W,
+    breaker: &CircuitBreaker,
+    mut op: F,
+) -> Result<T, CircuitError<E>>
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let op = || {
+        let call = breaker.acquire().map(|_| op());
+        async move {
+            match call {
+                Ok(call) => breaker.record(call.await),
+                Err(e) => Err(e),
+            }
+        }
+    };
+    retry_async_if(waiter, op, breaker_decision).await
+}
+
+/// Run an operation, and start another attempt each time the waiter's delay elapses without
+/// an attempt completing, until the waiter gives up. Returns the output of the first attempt
+/// to complete; the other attempts are dropped, which cancels them.
+///
+/// This trades extra load for lower tail latency. The waiter sets how many attempts can run,
+/// e.g. `Delay::builder().throttle(delay).max_attempts(2).build()` runs at most 3.
+#[cfg(feature = "async")]
+pub async fn hedge<W, T, F, Fut>(waiter: &mut W, mut op: F) -> T
+where
+    W: Waiter + ?Sized,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    use futures_util::future::{select, Either};
+    use futures_util::stream::{FuturesUnordered, StreamExt};
+
+    waiter.start();
+    let mut attempts = FuturesUnordered::new();
+    attempts.push(op());
+    loop {
+        let hedge = match select(attempts.next(), waiter.async_wait()).await {
+            Either::Left((output, _)) => return output.expect("an attempt is running"),
+            Either::Right((result, _)) => result.is_ok(),
+        };
+        if !hedge {
+            break;
+        }
+        attempts.push(op());
+    }
+    attempts.next().await.expect("an attempt is running")
+}
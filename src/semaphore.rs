@@ -0,0 +1,213 @@
+#![cfg(not(feature = "no_std"))]
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Condvar, Mutex};
+
+#[cfg(feature = "async")]
+use crate::wakers::{WakerSlot, Wakers};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+struct SemaphoreState {
+    max: usize,
+    /// The number of free permits.
+    available: Mutex<usize>,
+    freed: Condvar,
+    /// The wakers of the async acquisitions waiting for a permit.
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A counting semaphore, to limit the number of concurrent operations rather than their
+/// rate. Unlike a [crate::Bulkhead], acquiring blocks until a permit is released instead of
+/// polling with a waiter.
+///
+/// Clones share the same permits.
+#[derive(Clone)]
+pub struct Semaphore {
+    state: Arc<SemaphoreState>,
+}
+
+/// A permit of a [Semaphore], released when dropped.
+pub struct SemaphorePermit {
+    state: Arc<SemaphoreState>,
+}
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        *self.state.available.lock().unwrap() += 1;
+        #[cfg(feature = "async")]
+        self.state.wakers.wake_all();
+        self.state.freed.notify_one();
+    }
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Arc::new(SemaphoreState {
+                max: permits,
+                available: Mutex::new(permits),
+                freed: Condvar::new(),
+                #[cfg(feature = "async")]
+                wakers: Wakers::default(),
+            }),
+        }
+    }
+
+    /// The number of free permits.
+    pub fn available(&self) -> usize {
+        *self.state.available.lock().unwrap()
+    }
+
+    /// Take a permit if one is free.
+    pub fn try_acquire(&self) -> Option<SemaphorePermit> {
+        let mut available = self.state.available.lock().unwrap();
+        if *available == 0 {
+            return None;
+        }
+        *available -= 1;
+        Some(self.permit())
+    }
+
+    /// Take a permit, blocking until one is free.
+    pub fn acquire(&self) -> SemaphorePermit {
+        let mut available = self.state.available.lock().unwrap();
+        while *available == 0 {
+            available = self.state.freed.wait(available).unwrap();
+        }
+        *available -= 1;
+        self.permit()
+    }
+
+    /// Async version of [Semaphore::acquire].
+    #[cfg(feature = "async")]
+    pub async fn acquire_async(&self) -> SemaphorePermit {
+        AcquireFuture {
+            semaphore: self.clone(),
+            slot: self.state.wakers.slot(),
+        }
+        .await
+    }
+
+    /// A waiter holding a permit of this semaphore between its waits, see
+    /// [SemaphoreWaiter].
+    pub fn waiter(&self) -> SemaphoreWaiter {
+        SemaphoreWaiter::new(self.clone())
+    }
+
+    fn permit(&self) -> SemaphorePermit {
+        SemaphorePermit {
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// A future that resolves with a permit once one is free.
+#[cfg(feature = "async")]
+struct AcquireFuture {
+    semaphore: Semaphore,
+    slot: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl Future for AcquireFuture {
+    type Output = SemaphorePermit;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore.clone();
+        let mut available = semaphore.state.available.lock().unwrap();
+        if *available > 0 {
+            *available -= 1;
+            self.slot.remove();
+            return Poll::Ready(semaphore.permit());
+        }
+        self.slot.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for AcquireFuture {
+    fn drop(&mut self) {
+        if self.slot.is_registered() {
+            self.slot.remove();
+            // This future may have been woken for a permit it won't take, pass it on.
+            if *self.semaphore.state.available.lock().unwrap() > 0 {
+                self.semaphore.state.wakers.wake_all();
+            }
+        }
+    }
+}
+
+/// A waiter gated by a [Semaphore]: each wait releases the permit taken by the previous one,
+/// then blocks until a permit is free and holds it. The permit is released when the waiter
+/// is started again or dropped, or can be taken out as a guard with
+/// [SemaphoreWaiter::take_permit]. This never gives up.
+///
+/// Clones hold their own permit.
+pub struct SemaphoreWaiter {
+    semaphore: Semaphore,
+    /// Shared with the async waits, which store the permit they acquire.
+    permit: Arc<Mutex<Option<SemaphorePermit>>>,
+}
+impl Clone for SemaphoreWaiter {
+    fn clone(&self) -> Self {
+        Self::new(self.semaphore.clone())
+    }
+}
+impl SemaphoreWaiter {
+    pub fn new(semaphore: Semaphore) -> Self {
+        Self {
+            semaphore,
+            permit: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Take the permit acquired by the last wait, to release it when the guard is dropped.
+    pub fn take_permit(&mut self) -> Option<SemaphorePermit> {
+        self.permit.lock().unwrap().take()
+    }
+
+    pub fn holds_permit(&self) -> bool {
+        self.permit.lock().unwrap().is_some()
+    }
+}
+impl Waiter for SemaphoreWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.take_permit();
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.take_permit();
+        let permit = self.semaphore.acquire();
+        *self.permit.lock().unwrap() = Some(permit);
+        Ok(())
+    }
+    fn resume(&mut self, _state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "semaphore({} permits)", self.semaphore.state.max)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        self.take_permit();
+        let semaphore = self.semaphore.clone();
+        let slot = self.permit.clone();
+        Box::pin(async move {
+            let permit = semaphore.acquire_async().await;
+            *slot.lock().unwrap() = Some(permit);
+            Ok(())
+        })
+    }
+}
+
+describe_waiter!(SemaphoreWaiter);
@@ -0,0 +1,129 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+struct SharedState<W> {
+    inner: W,
+    started: bool,
+    until: Option<Instant>,
+}
+
+/// A backoff shared by workers using the same dependency. Clones share one inner waiter:
+/// every failed attempt of any worker advances it, and each wait lasts until the latest
+/// deadline any worker was given, so the workers slow down together instead of each
+/// retrying on its own schedule.
+///
+/// Only the first start starts the inner waiter, and restarting a clone doesn't reset the
+/// others; call [SharedBackoff::report_success] once the dependency works again.
+pub struct SharedBackoff<W: Waiter> {
+    state: Arc<Mutex<SharedState<W>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<W: Waiter> Clone for SharedBackoff<W> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<W: Waiter> SharedBackoff<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SharedState {
+                inner,
+                started: false,
+                until: None,
+            })),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Reset the shared backoff for all the workers.
+    pub fn report_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.inner.start();
+        state.started = true;
+        state.until = None;
+    }
+
+    /// The time until the workers may try again.
+    pub fn remaining(&self) -> Duration {
+        match self.state.lock().unwrap().until {
+            Some(until) => until.saturating_duration_since(self.clock.now()),
+            None => Duration::from_secs(0),
+        }
+    }
+}
+
+impl<W: 'static + Waiter> Waiter for SharedBackoff<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        if self.state.lock().unwrap().started {
+            Ok(())
+        } else {
+            Err(WaiterError::NotStarted)
+        }
+    }
+    fn start(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.started {
+            state.inner.start();
+            state.started = true;
+        }
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let mut state = self.state.lock().unwrap();
+        let delay = state.inner.advance()?;
+        let now = self.clock.now();
+        let deadline = deadline_after(now, delay);
+        let until = state.until.map_or(deadline, |until| until.max(deadline));
+        state.until = Some(until);
+        Ok(until - now)
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.state.lock().unwrap().inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.state.lock().unwrap().inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.state.lock().unwrap().inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.state.lock().unwrap().inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.state.lock().unwrap().inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        let mut shared = self.state.lock().unwrap();
+        shared.inner.resume(state)?;
+        shared.started = true;
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("shared(")?;
+        self.state.lock().unwrap().inner.describe(f)?;
+        f.write_str(")")
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(SharedBackoff<W>);
@@ -0,0 +1,205 @@
+use core::time::Duration;
+
+#[cfg(feature = "no_std")]
+use crate::WaiterError;
+#[cfg(all(feature = "async", feature = "no_std"))]
+use alloc::boxed::Box;
+#[cfg(feature = "no_std")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(all(feature = "async", feature = "no_std"))]
+use core::{future::Future, pin::Pin};
+
+/// A way to block for a duration, for [crate::WaiterExt::wait_with]. This is how waiters
+/// sleep with `no_std`, e.g. with a hardware timer, and is implemented for closures.
+pub trait Sleep {
+    fn sleep(&mut self, duration: Duration);
+}
+
+impl<F: FnMut(Duration)> Sleep for F {
+    fn sleep(&mut self, duration: Duration) {
+        self(duration)
+    }
+}
+
+/// Sleep with an `embedded-hal` delay provider.
+#[cfg(feature = "embedded-hal")]
+pub struct HalDelay<D>(pub D);
+
+#[cfg(feature = "embedded-hal")]
+impl<D: embedded_hal::delay::DelayNs> Sleep for HalDelay<D> {
+    fn sleep(&mut self, duration: Duration) {
+        // The delay takes u32 nanoseconds, so long durations take multiple calls.
+        let mut nanos = duration.as_nanos();
+        while nanos > 0 {
+            let chunk = core::cmp::min(nanos, u32::MAX as u128) as u32;
+            self.0.delay_ns(chunk);
+            nanos -= chunk as u128;
+        }
+    }
+}
+
+/// A blocking sleep for [set_sleep], e.g. with a hardware timer.
+#[cfg(feature = "no_std")]
+pub type SleepFn = fn(Duration);
+
+/// The [SleepFn] set with [set_sleep], or null.
+#[cfg(feature = "no_std")]
+static SLEEP: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Set how waiters sleep in [crate::Waiter::wait] with `no_std`. This is process-wide.
+/// Without it, blocking waits only advance the waiter, so a loop of waits doesn't wait at
+/// all; use [crate::WaiterExt::wait_with] to give the sleep of each wait instead.
+#[cfg(feature = "no_std")]
+pub fn set_sleep(sleep: SleepFn) {
+    SLEEP.store(sleep as *mut (), Ordering::Release);
+}
+
+/// Sleep for the delay with the sleep set with [set_sleep], if any, or return the error.
+/// This is how waiters wait with `no_std`.
+#[cfg(feature = "no_std")]
+pub(crate) fn no_std_block(delay: Result<Duration, WaiterError>) -> Result<(), WaiterError> {
+    let delay = delay?;
+    let sleep = SLEEP.load(Ordering::Acquire);
+    if !sleep.is_null() {
+        // Safety: only function pointers of this type are stored, see set_sleep.
+        let sleep = unsafe { core::mem::transmute::<*mut (), SleepFn>(sleep) };
+        sleep(delay);
+    }
+    Ok(())
+}
+
+/// An async sleep for [set_async_sleep], e.g. the timer of the executor.
+#[cfg(all(feature = "async", feature = "no_std"))]
+pub type AsyncSleepFn = fn(Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The [AsyncSleepFn] set with [set_async_sleep], or null.
+#[cfg(all(feature = "async", feature = "no_std"))]
+static ASYNC_SLEEP: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Set how waiters sleep in [crate::Waiter::async_wait] with `no_std`, for executors without
+/// an integration (e.g. RTIC or a custom one). This is process-wide, and takes precedence
+/// over the `embassy` feature. Without either, async waits only advance the waiter, like
+/// blocking waits.
+#[cfg(all(feature = "async", feature = "no_std"))]
+pub fn set_async_sleep(sleep: AsyncSleepFn) {
+    ASYNC_SLEEP.store(sleep as *mut (), Ordering::Release);
+}
+
+#[cfg(all(feature = "async", feature = "no_std"))]
+fn async_sleep_fn() -> Option<AsyncSleepFn> {
+    let sleep = ASYNC_SLEEP.load(Ordering::Acquire);
+    if sleep.is_null() {
+        None
+    } else {
+        // Safety: only function pointers of this type are stored, see set_async_sleep.
+        Some(unsafe { core::mem::transmute::<*mut (), AsyncSleepFn>(sleep) })
+    }
+}
+
+/// A future that resolves after the delay, or right away with the error. This is how
+/// waiters wait asynchronously with `no_std`, without threads: with the sleep set with
+/// [set_async_sleep], or else the `embassy-time` timer.
+#[cfg(all(feature = "async", feature = "no_std"))]
+pub(crate) fn no_std_sleep(
+    delay: Result<Duration, WaiterError>,
+) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+    let delay = match delay {
+        Ok(delay) => delay,
+        Err(e) => return Box::pin(futures_util::future::err(e)),
+    };
+    if let Some(sleep) = async_sleep_fn() {
+        let sleep = sleep(delay);
+        return Box::pin(async move {
+            sleep.await;
+            Ok(())
+        });
+    }
+    #[cfg(feature = "embassy")]
+    {
+        Box::pin(async move {
+            let micros = core::cmp::min(delay.as_micros(), u64::MAX as u128) as u64;
+            embassy_time::Timer::after(embassy_time::Duration::from_micros(micros)).await;
+            Ok(())
+        })
+    }
+    #[cfg(not(feature = "embassy"))]
+    {
+        Box::pin(futures_util::future::ok(()))
+    }
+}
+
+#[cfg(all(test, feature = "no_std"))]
+mod tests {
+    use super::*;
+    use crate::{ExponentialBackoffWaiter, ThrottleWaiter, Waiter};
+    use core::sync::atomic::AtomicU64;
+    #[cfg(feature = "async")]
+    use core::{
+        sync::atomic::AtomicBool,
+        task::{Context, Poll},
+    };
+
+    #[cfg(feature = "async")]
+    static SLEPT_MS: AtomicU64 = AtomicU64::new(0);
+    #[cfg(feature = "async")]
+    static AWAITED: AtomicBool = AtomicBool::new(false);
+    static BLOCKED_MS: AtomicU64 = AtomicU64::new(0);
+
+    fn record_block(duration: Duration) {
+        BLOCKED_MS.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn waits_block_with_the_sleep_set() {
+        set_sleep(record_block);
+
+        let mut waiter = ThrottleWaiter::new(Duration::from_millis(20));
+        waiter.start();
+        waiter.wait().unwrap();
+        waiter.wait().unwrap();
+        assert_eq!(BLOCKED_MS.load(Ordering::SeqCst), 40);
+
+        let mut backoff =
+            ExponentialBackoffWaiter::new(Duration::from_millis(10), 2.0, Duration::from_secs(1));
+        backoff.start();
+        backoff.wait().unwrap();
+        backoff.wait().unwrap();
+        assert_eq!(BLOCKED_MS.load(Ordering::SeqCst), 70);
+    }
+
+    #[cfg(feature = "async")]
+    struct RecordSleep;
+    #[cfg(feature = "async")]
+    impl Future for RecordSleep {
+        type Output = ();
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            AWAITED.store(true, Ordering::SeqCst);
+            Poll::Ready(())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn record_sleep(duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        SLEPT_MS.store(duration.as_millis() as u64, Ordering::SeqCst);
+        Box::pin(RecordSleep)
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_waits_await_the_sleep_set() {
+        set_async_sleep(record_sleep);
+        let sleep = async_sleep_fn().unwrap();
+        assert_eq!(sleep as usize, record_sleep as AsyncSleepFn as usize);
+
+        let mut waiter = ThrottleWaiter::new(Duration::from_millis(20));
+        waiter.start();
+        let mut wait = waiter.async_wait();
+        assert_eq!(SLEPT_MS.load(Ordering::SeqCst), 20);
+        assert!(!AWAITED.load(Ordering::SeqCst));
+
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(wait.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        assert!(AWAITED.load(Ordering::SeqCst));
+    }
+}
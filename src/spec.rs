@@ -0,0 +1,240 @@
+#![cfg(not(feature = "no_std"))]
+use crate::{Delay, DelayPolicy, Jitter};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// An error parsing a delay spec, with the part of the spec that is invalid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsePolicyError {
+    part: String,
+    reason: String,
+}
+
+impl ParsePolicyError {
+    fn new<R: Into<String>>(part: &str, reason: R) -> Self {
+        Self {
+            part: part.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// The part of the spec that could not be parsed.
+    pub fn part(&self) -> &str {
+        &self.part
+    }
+}
+
+impl fmt::Display for ParsePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid delay spec `{}`: {}", self.part, self.reason)
+    }
+}
+
+impl std::error::Error for ParsePolicyError {}
+
+fn duration(part: &str, s: &str) -> Result<Duration, ParsePolicyError> {
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let unit_nanos: u64 = match unit {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60_000_000_000,
+        "h" => 3_600_000_000_000,
+        "" => return Err(ParsePolicyError::new(part, format!("`{}` has no unit", s))),
+        _ => {
+            return Err(ParsePolicyError::new(
+                part,
+                format!("unknown unit `{}`", unit),
+            ))
+        }
+    };
+    let invalid = || ParsePolicyError::new(part, format!("invalid duration `{}`", s));
+    let nanos = if value.contains('.') {
+        let value = value.parse::<f64>().map_err(|_| invalid())? * unit_nanos as f64;
+        if value > u64::MAX as f64 {
+            return Err(invalid());
+        }
+        value.round() as u64
+    } else {
+        let value = value.parse::<u64>().map_err(|_| invalid())?;
+        value.checked_mul(unit_nanos).ok_or_else(invalid)?
+    };
+    Ok(Duration::from_nanos(nanos))
+}
+
+fn number<T: FromStr>(part: &str, s: &str) -> Result<T, ParsePolicyError> {
+    s.parse()
+        .map_err(|_| ParsePolicyError::new(part, format!("invalid number `{}`", s)))
+}
+
+/// Split `<args><=<cap>` into the args and the optional cap.
+fn cap(part: &str, args: &str) -> Result<(String, Option<Duration>), ParsePolicyError> {
+    match args.find("<=") {
+        Some(i) => Ok((args[..i].to_string(), Some(duration(part, &args[i + 2..])?))),
+        None => Ok((args.to_string(), None)),
+    }
+}
+
+/// Split `<a><separator><b>`, failing with the expected syntax.
+fn pair<'a>(
+    part: &str,
+    args: &'a str,
+    separator: char,
+    expected: &str,
+) -> Result<(&'a str, &'a str), ParsePolicyError> {
+    let i = args
+        .find(separator)
+        .ok_or_else(|| ParsePolicyError::new(part, format!("expected {}", expected)))?;
+    Ok((&args[..i], &args[i + 1..]))
+}
+
+fn policy(part: &str, name: &str, args: &str) -> Result<DelayPolicy, ParsePolicyError> {
+    let (args, cap) = cap(part, args)?;
+    let max = Duration::from_secs(u64::MAX);
+    let no_cap = |policy| match cap {
+        Some(_) => Err(ParsePolicyError::new(part, "this policy has no cap")),
+        None => Ok(policy),
+    };
+    match name {
+        "instant" if args.is_empty() => no_cap(DelayPolicy::Instant),
+        "throttle" => no_cap(DelayPolicy::Throttle(duration(part, &args)?)),
+        "exp" | "exponential" => {
+            let (initial, multiplier) = pair(part, &args, '*', "<initial>*<multiplier>")?;
+            Ok(DelayPolicy::ExponentialBackoff {
+                initial: duration(part, initial)?,
+                multiplier: number(part, multiplier)?,
+                cap,
+            })
+        }
+        "linear" => {
+            let (initial, increment) = pair(part, &args, '+', "<initial>+<increment>")?;
+            Ok(DelayPolicy::LinearBackoff {
+                initial: duration(part, initial)?,
+                increment: duration(part, increment)?,
+                cap: cap.unwrap_or(max),
+            })
+        }
+        "poly" | "polynomial" => {
+            let (initial, exponent) = pair(part, &args, '^', "<initial>^<exponent>")?;
+            Ok(DelayPolicy::PolynomialBackoff {
+                initial: duration(part, initial)?,
+                exponent: number(part, exponent)?,
+                cap: cap.unwrap_or(max),
+            })
+        }
+        "fib" | "fibonacci" => Ok(DelayPolicy::FibonacciBackoff {
+            initial: duration(part, &args)?,
+            cap: cap.unwrap_or(max),
+        }),
+        "decorrelated" => Ok(DelayPolicy::DecorrelatedJitter {
+            base: duration(part, &args)?,
+            cap: cap.unwrap_or(max),
+        }),
+        "fixed" => no_cap(DelayPolicy::FixedSchedule(
+            args.split('|')
+                .map(|d| duration(part, d))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Err(ParsePolicyError::new(
+            part,
+            format!("unknown policy `{}`", name),
+        )),
+    }
+}
+
+/// Parse a delay spec: comma separated policies, which are composed, followed by options.
+///
+/// Policies are `instant`, `throttle:<d>`, `exp:<d>*<multiplier>`, `linear:<d>+<d>`,
+/// `poly:<d>^<exponent>`, `fib:<d>`, `decorrelated:<d>` and `fixed:<d>|<d>|...`. Backoffs
+/// can be capped with `<=<d>`. Durations have a unit, one of `ns`, `us`, `ms`, `s`, `m` or
+/// `h`.
+///
+/// Options are `jitter=full|equal`, `max_attempts=<n>` and `timeout=<d>`, and apply to all
+/// the policies in that order, regardless of their order in the spec.
+///
+/// For example `exp:100ms*2<=30s,max_attempts=8,jitter=full`.
+impl FromStr for DelayPolicy {
+    type Err = ParsePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policies = Vec::new();
+        let mut jitter = None;
+        let mut max_attempts = None;
+        let mut timeout = None;
+
+        for part in s.split(',').map(str::trim) {
+            let split = part.find(&[':', '='][..]);
+            let (name, args) = match split {
+                Some(i) => (&part[..i], &part[i + 1..]),
+                None => (part, ""),
+            };
+            if name.is_empty() {
+                return Err(ParsePolicyError::new(
+                    part,
+                    "expected a policy or an option",
+                ));
+            }
+            if split.map(|i| &part[i..i + 1]) != Some("=") {
+                policies.push(policy(part, name, args)?);
+                continue;
+            }
+
+            let duplicate = match name {
+                "jitter" => jitter
+                    .replace(match args {
+                        "full" => Jitter::Full,
+                        "equal" => Jitter::Equal,
+                        _ => return Err(ParsePolicyError::new(part, "expected `full` or `equal`")),
+                    })
+                    .is_some(),
+                "max_attempts" => max_attempts.replace(number(part, args)?).is_some(),
+                "timeout" => timeout.replace(duration(part, args)?).is_some(),
+                _ => {
+                    return Err(ParsePolicyError::new(
+                        part,
+                        format!("unknown option `{}`", name),
+                    ))
+                }
+            };
+            if duplicate {
+                return Err(ParsePolicyError::new(part, "option given more than once"));
+            }
+        }
+
+        let mut policy = match policies.len() {
+            0 => DelayPolicy::Instant,
+            1 => policies.remove(0),
+            _ => DelayPolicy::Compose(policies),
+        };
+        if let Some(jitter) = jitter {
+            policy = DelayPolicy::Jitter {
+                jitter,
+                inner: Box::new(policy),
+            };
+        }
+        if let Some(max_attempts) = max_attempts {
+            policy = DelayPolicy::MaxAttempts {
+                max_attempts,
+                inner: Box::new(policy),
+            };
+        }
+        if let Some(timeout) = timeout {
+            policy = DelayPolicy::Compose(vec![DelayPolicy::Timeout(timeout), policy]);
+        }
+        Ok(policy)
+    }
+}
+
+/// Parse a delay from a spec, see [DelayPolicy::from_str].
+impl FromStr for Delay {
+    type Err = ParsePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<DelayPolicy>()?.build())
+    }
+}
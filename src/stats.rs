@@ -0,0 +1,159 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{sleep, Clock, SystemClock};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A summary of the waits recorded by [WaitStats]. Waits that ended early count as no
+/// overshoot.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct WaitSummary {
+    pub count: usize,
+    /// The total time the waits were asked to last.
+    pub requested: Duration,
+    /// The total time the waits actually lasted.
+    pub actual: Duration,
+    pub mean_overshoot: Duration,
+    pub max_overshoot: Duration,
+}
+
+#[derive(Default)]
+struct StatsInner {
+    count: u32,
+    requested: Duration,
+    actual: Duration,
+    overshoot: Duration,
+    max_overshoot: Duration,
+}
+
+/// A collector of how long waits lasted compared to their delay, e.g. to measure how much
+/// the scheduler oversleeps. Clones share the same record, so one collector can be given to
+/// many waiters, see [StatsWaiter].
+#[derive(Clone, Default)]
+pub struct WaitStats {
+    inner: Arc<Mutex<StatsInner>>,
+}
+
+impl WaitStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, requested: Duration, actual: Duration) {
+        let overshoot = actual.checked_sub(requested).unwrap_or_default();
+        let mut inner = self.inner.lock().unwrap();
+        inner.count = inner.count.saturating_add(1);
+        inner.requested += requested;
+        inner.actual += actual;
+        inner.overshoot += overshoot;
+        inner.max_overshoot = inner.max_overshoot.max(overshoot);
+    }
+
+    pub fn summary(&self) -> WaitSummary {
+        let inner = self.inner.lock().unwrap();
+        WaitSummary {
+            count: inner.count as usize,
+            requested: inner.requested,
+            actual: inner.actual,
+            mean_overshoot: inner.overshoot.checked_div(inner.count).unwrap_or_default(),
+            max_overshoot: inner.max_overshoot,
+        }
+    }
+
+    /// Forget the waits recorded so far.
+    pub fn reset(&self) {
+        *self.inner.lock().unwrap() = StatsInner::default();
+    }
+}
+
+/// A waiter that records the delay and the actual duration of its waits in a [WaitStats].
+/// The waits are measured and slept with the clock of this waiter, which
+/// [Waiter::set_clock] also sets.
+#[derive(Clone)]
+pub struct StatsWaiter<W: Waiter> {
+    inner: W,
+    stats: WaitStats,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> StatsWaiter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_stats(inner, WaitStats::new())
+    }
+
+    /// Create a waiter recording its waits in an existing collector.
+    pub fn with_stats(inner: W, stats: WaitStats) -> Self {
+        Self {
+            inner,
+            stats,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn stats(&self) -> WaitStats {
+        self.stats.clone()
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for StatsWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        let delay = self.advance();
+        let requested = *delay.as_ref().unwrap_or(&Duration::from_secs(0));
+        let start = self.clock.now();
+        sleep(delay, self.clock.as_ref(), self)?;
+        let actual = self.clock.now().saturating_duration_since(start);
+        self.stats.record(requested, actual);
+        Ok(())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.inner.advance()
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        let delay = self.advance();
+        let requested = *delay.as_ref().unwrap_or(&Duration::from_secs(0));
+        let clock = self.clock.clone();
+        let stats = self.stats.clone();
+        let start = clock.now();
+        let sleep = async_sleep(delay, clock.as_ref(), self);
+        Box::pin(async move {
+            sleep.await?;
+            stats.record(requested, clock.now().saturating_duration_since(start));
+            Ok(())
+        })
+    }
+}
+
+describe_waiter!(StatsWaiter<W>);
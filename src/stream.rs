@@ -0,0 +1,78 @@
+#![cfg(feature = "async")]
+use crate::{Waiter, WaiterError};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_util::stream::Stream;
+
+#[cfg(feature = "no_std")]
+use alloc::boxed::Box;
+
+type WaitFuture = Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>>;
+
+/// A stream that waits with its waiter before pulling each item of the inner stream. The
+/// stream ends when the inner stream ends, or when the waiter gives up.
+pub struct ThrottledStream<S, W: Waiter> {
+    stream: Pin<Box<S>>,
+    waiter: W,
+    wait: Option<WaitFuture>,
+    /// Whether the wait for the next item is over, while the inner stream is pending.
+    waited: bool,
+    done: bool,
+}
+impl<S: Stream, W: Waiter> ThrottledStream<S, W> {
+    /// The waiter is started right away.
+    pub fn new(stream: S, mut waiter: W) -> Self {
+        waiter.start();
+        Self {
+            stream: Box::pin(stream),
+            waiter,
+            wait: None,
+            waited: false,
+            done: false,
+        }
+    }
+}
+// The inner stream is boxed, and nothing else is ever pinned.
+impl<S, W: Waiter> Unpin for ThrottledStream<S, W> {}
+
+impl<S: Stream, W: Waiter> Stream for ThrottledStream<S, W> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        if !this.waited {
+            let waiter = &mut this.waiter;
+            let wait = this.wait.get_or_insert_with(|| waiter.async_wait());
+            match wait.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(_)) => {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+                Poll::Ready(Ok(())) => {}
+            }
+            // A finished wait can't be polled again.
+            this.wait = None;
+            this.waited = true;
+        }
+        let item = this.stream.as_mut().poll_next(cx);
+        if let Poll::Ready(item) = &item {
+            this.waited = false;
+            this.done = item.is_none();
+        }
+        item
+    }
+}
+
+/// Extension methods to pace streams with waiters.
+pub trait WaiterStreamExt: Stream + Sized {
+    /// Wait with the waiter before pulling each item, see [ThrottledStream].
+    fn throttle_with<W: Waiter>(self, waiter: W) -> ThrottledStream<Self, W> {
+        ThrottledStream::new(self, waiter)
+    }
+}
+impl<S: Stream> WaiterStreamExt for S {}
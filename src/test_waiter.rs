@@ -0,0 +1,86 @@
+#![cfg(all(not(feature = "no_std"), any(test, feature = "test-util")))]
+use crate::{InstantWaiter, Waiter, WaiterError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A test double that never sleeps, but records the delays it was asked to wait. The delays
+/// come from an inner waiter, so the code under test sees the same delays and errors as with
+/// the real policy, see [TestWaiter::wrap].
+///
+/// Clones share the same record, so a clone can be kept to check the delays once the waiter
+/// was given to the code under test.
+#[derive(Clone)]
+pub struct TestWaiter<W: Waiter = InstantWaiter> {
+    inner: W,
+    delays: Arc<Mutex<Vec<Duration>>>,
+}
+impl TestWaiter {
+    /// A test waiter that never gives up, recording zero delays.
+    pub fn new() -> Self {
+        Self::wrap(InstantWaiter)
+    }
+}
+impl Default for TestWaiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<W: Waiter> TestWaiter<W> {
+    pub fn wrap(inner: W) -> Self {
+        Self {
+            inner,
+            delays: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The delays waited, in order.
+    pub fn delays(&self) -> Vec<Duration> {
+        self.delays.lock().unwrap().clone()
+    }
+
+    /// The number of waits.
+    pub fn waits(&self) -> usize {
+        self.delays.lock().unwrap().len()
+    }
+}
+impl<W: 'static + Waiter + Clone> Waiter for TestWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        self.inner.restart()
+    }
+    fn start(&mut self) {
+        self.inner.start()
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.advance().map(|_| ())
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.inner.advance()?;
+        self.delays.lock().unwrap().push(delay);
+        Ok(delay)
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        self.inner.next_delay()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        self.inner.remaining()
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        Box::pin(futures_util::future::ready(self.wait()))
+    }
+}
+
+describe_waiter!(TestWaiter<W>);
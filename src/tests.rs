@@ -144,3 +144,2876 @@ async fn works_as_async() {
 
     rx_end.recv().await.unwrap();
 }
+
+#[test]
+fn jitter_works() {
+    use crate::{Jitter, JitterWaiter, ThrottleWaiter};
+
+    let mut full = JitterWaiter::new(
+        ThrottleWaiter::new(Duration::from_millis(100)),
+        Jitter::Full,
+    );
+    let mut equal = JitterWaiter::new(
+        ThrottleWaiter::new(Duration::from_millis(100)),
+        Jitter::Equal,
+    );
+    full.start();
+    equal.start();
+
+    for _ in 0..10 {
+        assert!(full.advance().unwrap() <= Duration::from_millis(100));
+        let delay = equal.advance().unwrap();
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    let start = Instant::now();
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(50))
+        .jitter(Jitter::Equal)
+        .build();
+    waiter.start();
+    waiter.wait().unwrap();
+    assert!(Instant::now().duration_since(start).as_millis() >= 25);
+}
+
+#[test]
+fn decorrelated_jitter_works() {
+    let mut waiter =
+        Delay::decorrelated_jitter(Duration::from_millis(10), Duration::from_millis(100));
+    assert!(waiter.advance().is_err());
+    waiter.start();
+
+    for _ in 0..20 {
+        let delay = waiter.advance().unwrap();
+        assert!(delay >= Duration::from_millis(10) && delay <= Duration::from_millis(100));
+    }
+    assert!(waiter.restart().is_ok());
+}
+
+#[test]
+fn fibonacci_backoff_works() {
+    let mut waiter = Delay::fibonacci_backoff(Duration::from_millis(1), Duration::from_millis(10));
+    waiter.start();
+
+    let delays: Vec<u128> = (0..8)
+        .map(|_| waiter.advance().unwrap().as_millis())
+        .collect();
+    assert_eq!(delays, vec![1, 1, 2, 3, 5, 8, 10, 10]);
+
+    waiter.restart().unwrap();
+    assert_eq!(waiter.advance().unwrap(), Duration::from_millis(1));
+}
+
+#[test]
+fn linear_backoff_works() {
+    let mut waiter = Delay::linear_backoff(
+        Duration::from_millis(5),
+        Duration::from_millis(10),
+        Duration::from_millis(30),
+    );
+    waiter.start();
+
+    let delays: Vec<u128> = (0..5)
+        .map(|_| waiter.advance().unwrap().as_millis())
+        .collect();
+    assert_eq!(delays, vec![5, 15, 25, 30, 30]);
+}
+
+#[test]
+fn polynomial_backoff_works() {
+    let mut waiter =
+        Delay::polynomial_backoff(Duration::from_millis(2), 2.0, Duration::from_millis(40));
+    waiter.start();
+
+    let delays: Vec<u128> = (0..6)
+        .map(|_| waiter.advance().unwrap().as_millis())
+        .collect();
+    assert_eq!(delays, vec![2, 8, 18, 32, 40, 40]);
+}
+
+#[test]
+fn fixed_schedule_works() {
+    let mut waiter = Delay::fixed_schedule(vec![
+        Duration::from_millis(1),
+        Duration::from_millis(5),
+        Duration::from_millis(2),
+    ]);
+    assert!(waiter.wait().is_err());
+    waiter.start();
+
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(1)));
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(5)));
+    assert!(waiter.wait().is_ok());
+    assert_eq!(
+        waiter.wait(),
+        Err(crate::WaiterError::Timeout {
+            elapsed: Duration::from_millis(8),
+            attempts: 3,
+        })
+    );
+
+    assert!(waiter.restart().is_ok());
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(1)));
+}
+
+#[test]
+fn max_attempts_works() {
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(2)
+        .build();
+    assert!(waiter.wait().is_err());
+    waiter.start();
+
+    assert!(waiter.wait().is_ok());
+    assert!(waiter.wait().is_ok());
+    assert_eq!(
+        waiter.wait(),
+        Err(crate::WaiterError::Timeout {
+            elapsed: Duration::from_secs(0),
+            attempts: 2,
+        })
+    );
+
+    assert!(waiter.restart().is_ok());
+    assert!(waiter.wait().is_ok());
+}
+
+#[test]
+fn deadline_works() {
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut waiter1 = Delay::deadline(deadline);
+    let mut waiter2 = Delay::builder()
+        .throttle(Duration::from_millis(10))
+        .deadline(deadline)
+        .build();
+    assert!(waiter1.wait().is_err());
+    waiter1.start();
+    waiter2.start();
+
+    assert!(waiter1.wait().is_ok());
+    assert!(waiter2.wait().is_ok());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(waiter1.restart().is_ok());
+    assert!(matches!(
+        waiter1.wait(),
+        Err(crate::WaiterError::Timeout { .. })
+    ));
+    assert!(matches!(
+        waiter2.wait(),
+        Err(crate::WaiterError::Timeout { .. })
+    ));
+}
+
+#[test]
+fn retry_works() {
+    let mut attempts = 0;
+    let mut waiter = Delay::count_timeout(5);
+    let result: Result<u32, &str> = crate::retry(&mut waiter, || {
+        attempts += 1;
+        if attempts < 3 {
+            Err("not yet")
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert_eq!(result, Ok(3));
+
+    // The waiter is restarted on every call, and the last error is returned.
+    attempts = 0;
+    let result: Result<(), u32> = crate::retry(&mut waiter, || {
+        attempts += 1;
+        Err(attempts)
+    });
+    assert_eq!(result, Err(6));
+}
+
+#[tokio::test]
+async fn retry_async_works() {
+    let attempts = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(5)
+        .build();
+
+    let result: Result<u32, u32> = crate::retry_async(&mut waiter, || {
+        let attempts = attempts.clone();
+        async move {
+            let current = attempts.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            if current < 3 {
+                Err(current)
+            } else {
+                Ok(current)
+            }
+        }
+    })
+    .await;
+    assert_eq!(result, Ok(3));
+
+    let result: Result<(), u32> = crate::retry_async(&mut waiter, || async { Err(1) }).await;
+    assert_eq!(result, Err(1));
+}
+
+#[tokio::test]
+async fn retry_async_cancels_attempts_that_overrun() {
+    use crate::{retry_async_with_attempt_timeout, AttemptError};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let attempts = std::sync::Arc::new(AtomicU32::new(0));
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(3)
+        .build();
+
+    // The first attempt hangs, and the second one succeeds.
+    let result: Result<u32, AttemptError<()>> =
+        retry_async_with_attempt_timeout(&mut waiter, Duration::from_millis(10), || {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+            async move {
+                if attempt == 1 {
+                    futures_util::future::pending::<()>().await;
+                }
+                Ok(attempt)
+            }
+        })
+        .await;
+    assert_eq!(result, Ok(2));
+
+    let result: Result<(), AttemptError<()>> =
+        retry_async_with_attempt_timeout(&mut waiter, Duration::from_millis(10), || {
+            futures_util::future::pending()
+        })
+        .await;
+    assert_eq!(result, Err(AttemptError::TimedOut));
+}
+
+#[tokio::test]
+async fn retry_macro_awaits_between_attempts() {
+    let mut attempts = 0;
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(5)
+        .build();
+    let result: Result<u32, u32> = crate::retry!(async waiter, {
+        attempts += 1;
+        futures_util::future::ready(()).await;
+        if attempts < 3 {
+            Err(attempts)
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert_eq!(result, Ok(3));
+}
+
+#[test]
+fn test_waiter_records_the_delays() {
+    use crate::{ExponentialBackoffWaiter, InstantWaiter, MaxAttemptsWaiter, TestWaiter};
+
+    let waiter = TestWaiter::wrap(MaxAttemptsWaiter::new(
+        ExponentialBackoffWaiter::new(Duration::from_secs(1), 2.0, Duration::from_secs(60)),
+        3,
+    ));
+    let mut retries = waiter.clone();
+    let result: Result<(), ()> = crate::retry(&mut retries, || Err(()));
+    assert_eq!(result, Err(()));
+    assert_eq!(
+        waiter.delays(),
+        vec![
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            Duration::from_secs(4)
+        ]
+    );
+
+    let mut waiter = TestWaiter::new();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(waiter.waits(), 3);
+    assert_eq!(InstantWaiter.to_string(), "instant");
+}
+
+#[test]
+fn fail_fast_never_retries() {
+    use crate::{FailFastWaiter, WaiterError};
+
+    let mut attempts = 0;
+    let mut waiter = Delay::fail_fast();
+    let result: Result<(), ()> = crate::retry(&mut waiter, || {
+        attempts += 1;
+        Err(())
+    });
+    assert_eq!(result, Err(()));
+    assert_eq!(attempts, 1);
+
+    let mut waiter = FailFastWaiter::with_error(WaiterError::Cancelled);
+    assert_eq!(waiter.wait(), Err(WaiterError::Cancelled));
+}
+
+#[test]
+fn retry_if_aborts_on_fatal_errors() {
+    use crate::RetryDecision;
+
+    let mut attempts = 0;
+    let mut waiter = Delay::count_timeout(5);
+    let result: Result<(), &str> = crate::retry_if(
+        &mut waiter,
+        || {
+            attempts += 1;
+            Err(if attempts < 2 { "transient" } else { "fatal" })
+        },
+        |e| {
+            if *e == "fatal" {
+                RetryDecision::Abort
+            } else {
+                RetryDecision::Retry
+            }
+        },
+    );
+    assert_eq!(result, Err("fatal"));
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn retry_macro_retries_an_expression() {
+    use crate::RetryDecision;
+
+    let mut attempts = 0;
+    let mut waiter = Delay::count_timeout(5);
+    let result: Result<usize, &str> = crate::retry!(waiter, {
+        attempts += 1;
+        if attempts < 3 {
+            Err("transient")
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert_eq!(result, Ok(3));
+
+    attempts = 0;
+    let result: Result<(), &str> = crate::retry!(
+        waiter,
+        {
+            attempts += 1;
+            Err(if attempts < 2 { "transient" } else { "fatal" })
+        },
+        |e| if *e == "fatal" {
+            RetryDecision::Abort
+        } else {
+            RetryDecision::Retry
+        }
+    );
+    assert_eq!(result, Err("fatal"));
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn then_works() {
+    use crate::{FixedScheduleWaiter, ThrottleWaiter, WaiterExt};
+
+    let mut waiter = FixedScheduleWaiter::new(vec![Duration::from_millis(1); 2])
+        .then(ThrottleWaiter::new(Duration::from_millis(7)));
+    waiter.start();
+
+    let delays: Vec<u128> = (0..4)
+        .map(|_| waiter.advance().unwrap().as_millis())
+        .collect();
+    assert_eq!(delays, vec![1, 1, 7, 7]);
+
+    waiter.restart().unwrap();
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(1)));
+}
+
+#[test]
+fn min_and_max_of_work() {
+    use crate::{LinearBackoffWaiter, MaxOf, MinOf, ThrottleWaiter};
+
+    let backoff = LinearBackoffWaiter::new(
+        Duration::from_millis(0),
+        Duration::from_millis(10),
+        Duration::from_millis(100),
+    );
+    let throttle = ThrottleWaiter::new(Duration::from_millis(15));
+
+    let mut min = MinOf::new(backoff.clone(), throttle.clone());
+    let mut max = MaxOf::new(backoff, throttle);
+    min.start();
+    max.start();
+
+    let delays: Vec<u128> = (0..3).map(|_| min.advance().unwrap().as_millis()).collect();
+    assert_eq!(delays, vec![0, 10, 15]);
+    let delays: Vec<u128> = (0..3).map(|_| max.advance().unwrap().as_millis()).collect();
+    assert_eq!(delays, vec![15, 15, 20]);
+
+    let mut failing = MaxOf::new(
+        Delay::count_timeout(1),
+        ThrottleWaiter::new(Duration::from_millis(1)),
+    );
+    failing.start();
+    assert!(failing.wait().is_ok());
+    assert!(failing.wait().is_err());
+}
+
+#[tokio::test]
+async fn throttle_works_as_async() {
+    let start = Instant::now();
+
+    let mut waiter = Delay::throttle(Duration::from_millis(50));
+    waiter.start();
+    waiter.async_wait().await.unwrap();
+
+    assert!(Instant::now().duration_since(start).as_millis() >= 50);
+}
+
+#[tokio::test]
+async fn wait_futures_can_be_stored_unboxed() {
+    use crate::{BackoffWait, ExponentialBackoffWaiter, ThrottleWait, ThrottleWaiter, WaiterError};
+
+    struct Pending {
+        throttle: ThrottleWait,
+        backoff: BackoffWait,
+    }
+
+    let start = Instant::now();
+    let mut throttle = ThrottleWaiter::new(Duration::from_millis(20));
+    let mut backoff =
+        ExponentialBackoffWaiter::new(Duration::from_millis(1), 2.0, Duration::from_secs(1));
+    throttle.start();
+    let pending = Pending {
+        throttle: throttle.wait_future(),
+        backoff: backoff.wait_future(),
+    };
+    pending.throttle.await.unwrap();
+    assert!(Instant::now().duration_since(start).as_millis() >= 20);
+    assert_eq!(pending.backoff.await, Err(WaiterError::NotStarted));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn zero_delay_async_waits_are_ready_on_first_poll() {
+    use crate::ThrottleWaiter;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    let mut waiter = ThrottleWaiter::new(Duration::from_secs(0));
+    waiter.start();
+    let mut wait = waiter.wait_future();
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(Pin::new(&mut wait).poll(&mut cx), Poll::Ready(Ok(())));
+}
+
+#[cfg(not(any(
+    feature = "wasm",
+    feature = "tokio",
+    feature = "async-std",
+    feature = "smol"
+)))]
+#[test]
+fn dropped_async_waits_leave_the_timer_thread() {
+    use crate::clock::pending_timers;
+    use crate::{Clock, SystemClock};
+    use std::task::Context;
+
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut waits: Vec<_> = (0..1000)
+        .map(|_| SystemClock.async_sleep(Duration::from_secs(3600)))
+        .collect();
+    for wait in &mut waits {
+        assert!(wait.as_mut().poll(&mut cx).is_pending());
+    }
+    assert!(pending_timers() >= 1000);
+    drop(waits);
+    assert!(
+        pending_timers() < 100,
+        "pending timers: {}",
+        pending_timers()
+    );
+}
+
+#[tokio::test]
+async fn many_async_waits_complete_in_order() {
+    let start = Instant::now();
+
+    let mut long = Delay::throttle(Duration::from_millis(60));
+    let mut short = Delay::throttle(Duration::from_millis(20));
+    long.start();
+    short.start();
+
+    let long = long.async_wait();
+    short.async_wait().await.unwrap();
+    assert!(Instant::now().duration_since(start).as_millis() < 60);
+    long.await.unwrap();
+    assert!(Instant::now().duration_since(start).as_millis() >= 60);
+}
+
+#[test]
+fn clock_can_be_replaced() {
+    use crate::Clock;
+    use std::sync::{Arc, Mutex};
+
+    struct RecordingClock(Mutex<Vec<Duration>>);
+    impl Clock for RecordingClock {
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+        fn sleep(&self, duration: Duration) {
+            self.0.lock().unwrap().push(duration);
+        }
+        #[cfg(feature = "async")]
+        fn async_sleep(
+            &self,
+            duration: Duration,
+        ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> {
+            self.sleep(duration);
+            Box::pin(async {})
+        }
+    }
+
+    let clock = Arc::new(RecordingClock(Mutex::new(Vec::new())));
+    let mut waiter = Delay::builder()
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .throttle(Duration::from_secs(10))
+        .clock(clock.clone())
+        .build();
+    waiter.start();
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+
+    let secs: Vec<u64> = clock
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| d.as_secs())
+        .collect();
+    assert_eq!(secs, vec![1, 10, 2, 10]);
+}
+
+#[test]
+fn mock_clock_works() {
+    use crate::{MockClock, WaiterError, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::builder()
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .timeout(Duration::from_secs(10))
+        .build()
+        .with_clock(clock.shared());
+    waiter.start();
+
+    // Waits 1 + 2 + 4 seconds, then 8 seconds which passes the timeout.
+    assert!(waiter.wait().is_ok());
+    assert!(waiter.wait().is_ok());
+    assert!(waiter.wait().is_ok());
+    assert_eq!(clock.elapsed(), Duration::from_secs(7));
+    assert_eq!(
+        waiter.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_secs(15),
+            attempts: 4,
+        })
+    );
+    assert_eq!(clock.elapsed(), Duration::from_secs(15));
+
+    // Manually advancing is visible to all waiters sharing the clock.
+    let mut timeout = crate::TimeoutWaiter::new(Duration::from_secs(5)).with_clock(clock.shared());
+    timeout.start();
+    assert!(timeout.wait().is_ok());
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(
+        timeout.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_secs(6),
+            attempts: 0,
+        })
+    );
+}
+
+#[test]
+fn cancel_interrupts_wait() {
+    use crate::{MockClock, WaiterError, WaiterExt};
+
+    let mut waiter = Delay::throttle(Duration::from_secs(30)).cancelable();
+    let handle = waiter.handle();
+    waiter.start();
+
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        handle.cancel();
+    });
+    assert_eq!(waiter.wait(), Err(WaiterError::Cancelled));
+    assert!(Instant::now().duration_since(start) < Duration::from_secs(1));
+    assert_eq!(waiter.wait(), Err(WaiterError::Cancelled));
+
+    // Blocking waits sleep with the clock of the waiter.
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(30))
+        .cancelable()
+        .with_clock(clock.shared());
+    waiter.start();
+    assert_eq!(waiter.wait(), Ok(()));
+    assert_eq!(clock.elapsed(), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn cancel_interrupts_async_wait() {
+    use crate::{WaiterError, WaiterExt};
+
+    let mut waiter = Delay::throttle(Duration::from_secs(30)).cancelable();
+    let handle = waiter.handle();
+    waiter.start();
+
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        handle.cancel();
+    });
+    assert_eq!(waiter.async_wait().await, Err(WaiterError::Cancelled));
+    assert!(Instant::now().duration_since(start) < Duration::from_secs(1));
+}
+
+#[test]
+fn notifier_wakes_waits_early() {
+    use crate::{MockClock, WaiterExt, Wakeup};
+
+    let mut waiter = Delay::throttle(Duration::from_secs(30)).notifiable();
+    let notifier = waiter.notifier();
+    waiter.start();
+
+    let start = Instant::now();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        notifier.notify();
+        notifier
+    });
+    assert_eq!(waiter.wait_notified(), Ok(Wakeup::Notified));
+    assert!(Instant::now().duration_since(start) < Duration::from_secs(1));
+
+    // A notification sent between waits makes the next one return right away.
+    thread.join().unwrap().notify();
+    assert_eq!(waiter.wait_notified(), Ok(Wakeup::Notified));
+
+    let mut waiter = Delay::throttle(Duration::from_millis(5)).notifiable();
+    waiter.start();
+    assert_eq!(waiter.wait_notified(), Ok(Wakeup::Elapsed));
+
+    // Blocking waits sleep with the clock of the waiter.
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(30))
+        .notifiable()
+        .with_clock(clock.shared());
+    waiter.start();
+    assert_eq!(waiter.wait_notified(), Ok(Wakeup::Elapsed));
+    assert_eq!(clock.elapsed(), Duration::from_secs(30));
+    waiter.notifier().notify();
+    assert_eq!(waiter.wait_notified(), Ok(Wakeup::Notified));
+    assert_eq!(clock.elapsed(), Duration::from_secs(30));
+}
+
+#[test]
+fn wait_or_recv_returns_early_on_messages() {
+    use crate::WaiterRecvExt;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut waiter = Delay::throttle(Duration::from_secs(30));
+    waiter.start();
+
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        sender.send("stop").unwrap();
+    });
+    assert_eq!(waiter.wait_or_recv(&receiver), Ok(Some("stop")));
+    assert!(Instant::now().duration_since(start) < Duration::from_secs(1));
+
+    let mut waiter = Delay::throttle(Duration::from_millis(5));
+    waiter.start();
+    assert_eq!(waiter.wait_or_recv(&receiver), Ok(None));
+
+    // A delay too long for an Instant still returns the messages.
+    let (sender, receiver) = std::sync::mpsc::channel();
+    sender.send("now").unwrap();
+    let mut waiter = crate::ThrottleWaiter::from_rate(0.0);
+    waiter.start();
+    assert_eq!(waiter.wait_or_recv(&receiver), Ok(Some("now")));
+}
+
+#[test]
+fn stats_record_wait_overshoot() {
+    use crate::{WaitStats, WaitSummary, WaiterExt};
+
+    let mut waiter = Delay::throttle(Duration::from_millis(5)).with_stats();
+    let stats = waiter.stats();
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    let summary = stats.summary();
+    assert_eq!(summary.count, 3);
+    assert_eq!(summary.requested, Duration::from_millis(15));
+    assert!(summary.actual >= summary.requested);
+    assert!(summary.max_overshoot >= summary.mean_overshoot);
+
+    let stats = WaitStats::new();
+    stats.record(Duration::from_millis(10), Duration::from_millis(14));
+    stats.record(Duration::from_millis(10), Duration::from_millis(8));
+    assert_eq!(
+        stats.summary(),
+        WaitSummary {
+            count: 2,
+            requested: Duration::from_millis(20),
+            actual: Duration::from_millis(22),
+            mean_overshoot: Duration::from_millis(2),
+            max_overshoot: Duration::from_millis(4),
+        }
+    );
+    stats.reset();
+    assert_eq!(stats.summary(), WaitSummary::default());
+}
+
+#[test]
+fn throttle_keeps_a_fixed_cadence() {
+    use crate::{MockClock, ThrottleWaiter};
+
+    let clock = MockClock::new();
+    let mut waiter = ThrottleWaiter::new(Duration::from_millis(100)).with_fixed_cadence();
+    waiter.set_clock(clock.shared());
+    waiter.start();
+
+    waiter.wait().unwrap();
+    clock.advance(Duration::from_millis(30));
+    assert_eq!(waiter.next_delay(), Some(Duration::from_millis(70)));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(200));
+
+    // Work longer than the throttle doesn't make up for it later.
+    clock.advance(Duration::from_millis(150));
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(0)));
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(100)));
+}
+
+#[test]
+fn throttle_allows_bursts_after_quiet_periods() {
+    use crate::{MockClock, ThrottleWaiter};
+
+    let clock = MockClock::new();
+    let mut waiter = ThrottleWaiter::new(Duration::from_millis(100)).with_burst(3);
+    waiter.set_clock(clock.shared());
+    waiter.start();
+
+    let mut delays = Vec::new();
+    for _ in 0..5 {
+        delays.push(waiter.advance().unwrap());
+    }
+    let ms = Duration::from_millis;
+    assert_eq!(delays, vec![ms(0), ms(0), ms(0), ms(100), ms(100)]);
+
+    // Two throttles of quiet time allow two more waits without waiting.
+    clock.advance(ms(100) + ms(200));
+    assert_eq!(waiter.advance(), Ok(ms(0)));
+    assert_eq!(waiter.advance(), Ok(ms(0)));
+    assert_eq!(waiter.advance(), Ok(ms(100)));
+}
+
+#[test]
+fn priority_waiters_share_the_rate_by_weight() {
+    use crate::{MockClock, Priority, PriorityWaiter};
+
+    let clock = MockClock::new();
+    let mut low = PriorityWaiter::new(10.0).for_priority(Priority::Low);
+    low.set_clock(clock.shared());
+    let mut high = low.for_priority(Priority::High);
+    let ms = Duration::from_millis;
+
+    // Alone, the low priority class gets the whole rate.
+    assert_eq!(low.advance(), Ok(ms(0)));
+    assert_eq!(low.advance(), Ok(ms(100)));
+    assert_eq!(low.advance(), Ok(ms(200)));
+
+    // High priority waits don't queue behind them, and get 4/5 of the rate.
+    assert_eq!(high.advance(), Ok(ms(0)));
+    assert_eq!(high.advance(), Ok(ms(125)));
+    assert_eq!(low.advance(), Ok(ms(300)));
+    assert_eq!(low.advance(), Ok(ms(800)));
+}
+
+#[test]
+fn priority_waiters_saturate_intervals_too_long_for_a_duration() {
+    use crate::{MockClock, Priority, PriorityWaiter};
+
+    let clock = MockClock::new();
+    let mut low = PriorityWaiter::new(1e-19).for_priority(Priority::Low);
+    low.set_clock(clock.shared());
+    let mut high = low.for_priority(Priority::High);
+
+    assert_eq!(low.advance(), Ok(Duration::from_secs(0)));
+    assert_eq!(high.advance(), Ok(Duration::from_secs(0)));
+    assert!(high.advance().unwrap() > Duration::from_secs(1_000_000_000));
+}
+
+#[test]
+fn fair_queue_returns_waits_in_order() {
+    use crate::{FixedScheduleWaiter, WaiterExt};
+    use std::sync::mpsc;
+
+    let ms = Duration::from_millis;
+    let mut waiter = FixedScheduleWaiter::new(vec![ms(50), ms(0)]).fair_queue();
+    waiter.start();
+    let (sender, receiver) = mpsc::channel();
+
+    let mut first = waiter.clone();
+    let first_sender = sender.clone();
+    let thread = std::thread::spawn(move || {
+        first.wait().unwrap();
+        first_sender.send("first").unwrap();
+    });
+    std::thread::sleep(ms(10));
+
+    // This wait has no delay, but returns after the one that started before it.
+    waiter.advance().unwrap();
+    waiter.wait().unwrap();
+    sender.send("second").unwrap();
+    thread.join().unwrap();
+    assert_eq!(
+        receiver.try_iter().collect::<Vec<_>>(),
+        vec!["first", "second"]
+    );
+}
+
+#[test]
+fn semaphore_waiter_blocks_until_a_permit_is_released() {
+    use crate::Semaphore;
+    use std::sync::mpsc;
+
+    let semaphore = Semaphore::new(1);
+    let mut waiter = semaphore.waiter();
+    waiter.start();
+    waiter.wait().unwrap();
+    assert!(waiter.holds_permit());
+    assert_eq!(semaphore.available(), 0);
+    assert!(semaphore.try_acquire().is_none());
+
+    let (sender, receiver) = mpsc::channel();
+    let mut other = semaphore.waiter();
+    let thread = std::thread::spawn(move || {
+        other.start();
+        other.wait().unwrap();
+        sender.send(()).unwrap();
+        other
+    });
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(receiver.try_recv().is_err());
+
+    let permit = waiter.take_permit();
+    assert!(permit.is_some() && !waiter.holds_permit());
+    drop(permit);
+    receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+    drop(thread.join().unwrap());
+    assert_eq!(semaphore.available(), 1);
+}
+
+#[test]
+fn throttles_from_rates() {
+    use crate::ThrottleWaiter;
+
+    assert_eq!(
+        ThrottleWaiter::from_rate(4.0).next_delay(),
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(
+        ThrottleWaiter::per_minute(30.0).next_delay(),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(
+        ThrottleWaiter::per_hour(1.0).next_delay(),
+        Some(Duration::from_secs(3600))
+    );
+    let mut forever = ThrottleWaiter::from_rate(0.0);
+    assert_eq!(forever.next_delay(), Some(Duration::from_secs(u64::MAX)));
+    forever.start();
+    assert_eq!(forever.advance(), Ok(Duration::from_secs(u64::MAX)));
+    assert_eq!(
+        Delay::builder()
+            .throttle_per_minute(120.0)
+            .build()
+            .next_delay(),
+        Some(Duration::from_millis(500))
+    );
+}
+
+#[test]
+fn shutdown_signal_cancels_all_waiters() {
+    use crate::{ShutdownSignal, WaiterError};
+
+    let signal = ShutdownSignal::new();
+    let threads: Vec<_> = (0..3)
+        .map(|_| {
+            let mut waiter = signal.register(Delay::throttle(Duration::from_secs(30)));
+            std::thread::spawn(move || {
+                waiter.start();
+                waiter.wait()
+            })
+        })
+        .collect();
+
+    std::thread::sleep(Duration::from_millis(20));
+    signal.trigger();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), Err(WaiterError::Cancelled));
+    }
+
+    // Waiters registered after the signal was triggered are cancelled right away.
+    let mut waiter = signal.register(Delay::instant());
+    waiter.start();
+    assert_eq!(waiter.wait(), Err(WaiterError::Cancelled));
+}
+
+#[test]
+fn waiters_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Delay>();
+    assert_send_sync::<Box<dyn Waiter>>();
+    assert_send_sync::<crate::ThrottleWaiter>();
+    assert_send_sync::<crate::ExponentialBackoffWaiter>();
+    assert_send_sync::<crate::TimeoutWaiter>();
+    assert_send_sync::<crate::DeadlineWaiter>();
+    assert_send_sync::<crate::FibonacciBackoffWaiter>();
+    assert_send_sync::<crate::JitterWaiter<Delay>>();
+    assert_send_sync::<crate::CancelableWaiter<Delay>>();
+}
+
+#[test]
+fn boxed_waiters_can_be_cloned() {
+    let mut registry: std::collections::HashMap<&str, Box<dyn Waiter>> =
+        std::collections::HashMap::new();
+    registry.insert("count", Box::new(Delay::count_timeout(1)));
+
+    let mut waiter1 = registry["count"].clone();
+    let mut waiter2 = registry["count"].clone();
+    waiter1.start();
+    waiter2.start();
+
+    assert!(waiter1.wait().is_ok());
+    assert!(waiter1.wait().is_err());
+    assert!(waiter2.wait().is_ok());
+}
+
+#[test]
+fn introspection_works() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::builder()
+        .timeout(Duration::from_secs(60))
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .max_attempts(10)
+        .build()
+        .with_clock(clock.shared());
+    assert_eq!(waiter.next_delay(), None);
+    waiter.start();
+
+    assert_eq!(waiter.attempts(), 0);
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(1)));
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+    assert_eq!(waiter.attempts(), 2);
+    assert_eq!(waiter.elapsed(), Duration::from_secs(3));
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(4)));
+
+    waiter.restart().unwrap();
+    assert_eq!(waiter.attempts(), 0);
+    assert_eq!(waiter.elapsed(), Duration::from_secs(0));
+}
+
+#[test]
+fn observed_waiter_calls_hooks() {
+    use crate::{MockClock, WaiterExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    let failures = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let (d, f, r) = (delays.clone(), failures.clone(), restarts.clone());
+
+    let mut waiter = Delay::builder()
+        .exponential_backoff(Duration::from_millis(10), 2.0)
+        .max_attempts(2)
+        .build()
+        .observed()
+        .before_wait(move |delay| d.lock().unwrap().push(delay))
+        .after_wait(move |result| {
+            if result.is_err() {
+                f.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .on_restart(move || {
+            r.fetch_add(1, Ordering::SeqCst);
+        })
+        .with_clock(MockClock::new().shared());
+    waiter.start();
+
+    assert!(waiter.wait().is_ok());
+    assert!(waiter.wait().is_ok());
+    assert!(waiter.wait().is_err());
+    waiter.restart().unwrap();
+
+    assert_eq!(
+        *delays.lock().unwrap(),
+        vec![Duration::from_millis(10), Duration::from_millis(20)]
+    );
+    assert_eq!(failures.load(Ordering::SeqCst), 1);
+    assert_eq!(restarts.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn policy_builds_delay() {
+    use crate::{DelayPolicy, Jitter, MockClock, WaiterError, WaiterExt};
+
+    let policy = DelayPolicy::Compose(vec![
+        DelayPolicy::Timeout(Duration::from_secs(10)),
+        DelayPolicy::MaxAttempts {
+            max_attempts: 3,
+            inner: Box::new(DelayPolicy::Jitter {
+                jitter: Jitter::Full,
+                inner: Box::new(DelayPolicy::ExponentialBackoff {
+                    initial: Duration::from_millis(100),
+                    multiplier: 2.0,
+                    cap: None,
+                }),
+            }),
+        },
+    ]);
+    let mut waiter = policy.build().with_clock(MockClock::new().shared());
+    waiter.start();
+    for _ in 0..3 {
+        assert!(waiter.wait().is_ok());
+    }
+    assert!(matches!(
+        waiter.wait(),
+        Err(WaiterError::Timeout { attempts: 3, .. })
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn policy_and_state_roundtrip_with_serde() {
+    use crate::{DelayPolicy, Jitter, WaiterState};
+
+    let policy = DelayPolicy::Compose(vec![
+        DelayPolicy::FixedSchedule(vec![Duration::from_millis(10), Duration::from_secs(1)]),
+        DelayPolicy::MaxAttempts {
+            max_attempts: 3,
+            inner: Box::new(DelayPolicy::Jitter {
+                jitter: Jitter::Full,
+                inner: Box::new(DelayPolicy::Throttle(Duration::from_millis(100))),
+            }),
+        },
+    ]);
+    let json = serde_json::to_string(&policy).unwrap();
+    assert_eq!(serde_json::from_str::<DelayPolicy>(&json).unwrap(), policy);
+
+    let state = WaiterState {
+        attempts: 4,
+        elapsed: Duration::from_millis(1500),
+    };
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(serde_json::from_str::<WaiterState>(&json).unwrap(), state);
+}
+
+#[test]
+fn policy_can_be_parsed() {
+    use crate::{DelayPolicy, Jitter};
+
+    let policy: DelayPolicy = "exp:100ms*2<=30s,max_attempts=8,jitter=full"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        policy,
+        DelayPolicy::MaxAttempts {
+            max_attempts: 8,
+            inner: Box::new(DelayPolicy::Jitter {
+                jitter: Jitter::Full,
+                inner: Box::new(DelayPolicy::ExponentialBackoff {
+                    initial: Duration::from_millis(100),
+                    multiplier: 2.0,
+                    cap: Some(Duration::from_secs(30)),
+                }),
+            }),
+        }
+    );
+    assert_eq!(
+        "throttle:1.5s, fixed:1s|2m, timeout=1h".parse::<DelayPolicy>(),
+        Ok(DelayPolicy::Compose(vec![
+            DelayPolicy::Timeout(Duration::from_secs(3600)),
+            DelayPolicy::Compose(vec![
+                DelayPolicy::Throttle(Duration::from_millis(1500)),
+                DelayPolicy::FixedSchedule(vec![Duration::from_secs(1), Duration::from_secs(120)]),
+            ]),
+        ]))
+    );
+
+    let err = "exp:100ms*2,jitter=half"
+        .parse::<DelayPolicy>()
+        .unwrap_err();
+    assert_eq!(err.part(), "jitter=half");
+    assert_eq!(
+        err.to_string(),
+        "invalid delay spec `jitter=half`: expected `full` or `equal`"
+    );
+    assert!("exp:100*2".parse::<Delay>().is_err());
+    assert!("linear:1s".parse::<Delay>().is_err());
+    assert!("exp:1s*2,max_attempts=1,max_attempts=2"
+        .parse::<Delay>()
+        .is_err());
+}
+
+#[test]
+fn builder_chains_everything() {
+    use crate::{Jitter, MockClock, WaiterError};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::builder()
+        .max_elapsed(Duration::from_secs(10))
+        .throttle(Duration::from_secs(1))
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .jitter(Jitter::Equal)
+        .max_attempts(100)
+        .clock(clock.shared())
+        .build();
+    waiter.start();
+
+    let mut waits = 0;
+    while waiter.wait().is_ok() {
+        waits += 1;
+    }
+    // Each wait is at least half of 1 + 2^n seconds.
+    assert!((3..=5).contains(&waits), "waits: {}", waits);
+    assert!(clock.elapsed() >= Duration::from_secs(10));
+
+    let mut waiter = Delay::builder()
+        .max_elapsed(Duration::from_secs(10))
+        .throttle(Duration::from_secs(1))
+        .max_attempts(3)
+        .clock(clock.shared())
+        .build();
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(
+        waiter.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_secs(3),
+            attempts: 3,
+        })
+    );
+}
+
+#[test]
+fn token_bucket_allows_bursts() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::token_bucket(10.0, 3).with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_secs(0));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(100));
+
+    // The bucket refills while idle, up to the burst size.
+    clock.advance(Duration::from_secs(10));
+    let mut other = waiter.clone();
+    for _ in 0..3 {
+        other.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_millis(10_100));
+    // Clones share the bucket.
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(10_200));
+}
+
+#[test]
+fn leaky_bucket_spaces_waits() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::leaky_bucket(4.0).with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_millis(500));
+
+    // Time spent between waits counts, and doesn't accumulate into a burst.
+    clock.advance(Duration::from_secs(1));
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(1750));
+}
+
+#[test]
+fn gcra_allows_bursts() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::gcra(10.0, 3).with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_secs(0));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(100));
+
+    clock.advance(Duration::from_secs(10));
+    let mut other = waiter.clone();
+    for _ in 0..3 {
+        other.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_millis(10_100));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(10_200));
+}
+
+#[test]
+fn rate_limiters_reject_rates_that_are_not_positive() {
+    use crate::{GcraWaiter, LeakyBucketWaiter, PriorityWaiter, TokenBucketWaiter};
+    use std::panic::catch_unwind;
+
+    for &rate in &[0.0, -1.0, f64::NAN] {
+        assert!(catch_unwind(|| TokenBucketWaiter::new(rate, 1)).is_err());
+        assert!(catch_unwind(|| LeakyBucketWaiter::new(rate)).is_err());
+        assert!(catch_unwind(|| GcraWaiter::new(rate, 1)).is_err());
+        assert!(catch_unwind(|| PriorityWaiter::new(rate)).is_err());
+    }
+}
+
+#[test]
+fn sliding_window_limits_calls() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::sliding_window(3, Duration::from_secs(60)).with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    clock.advance(Duration::from_secs(10));
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(10));
+
+    // Wait for the first call to leave the window, then for the second one.
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(60));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(70));
+}
+
+#[test]
+fn aimd_adapts_to_results() {
+    use crate::{AimdWaiter, MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let reporter = AimdWaiter::new(
+        Duration::from_millis(10),
+        Duration::from_secs(1),
+        Duration::from_millis(50),
+        4.0,
+    );
+    let mut waiter = Delay::from(Box::new(reporter.clone())).with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(10));
+
+    reporter.report_failure();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_millis(60)));
+    reporter.report_failure();
+    reporter.report_failure();
+    reporter.report_failure();
+    assert_eq!(reporter.current(), Duration::from_secs(1));
+
+    reporter.report_success();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(960));
+    for _ in 0..100 {
+        reporter.report_success();
+    }
+    assert_eq!(reporter.current(), Duration::from_millis(10));
+
+    // Without a cap, the delay grows to the longest one.
+    let uncapped = AimdWaiter::new(
+        Duration::from_secs(1),
+        Duration::from_secs(u64::MAX),
+        Duration::from_secs(1),
+        4.0,
+    );
+    for _ in 0..40 {
+        uncapped.report_failure();
+    }
+    assert_eq!(uncapped.current(), Duration::from_micros(u64::MAX));
+}
+
+#[test]
+fn retry_overall_timeout_counts_the_attempts() {
+    use crate::retry_with_overall_timeout;
+
+    // Each attempt takes 20ms, so the timeout passes long before the waiter gives up.
+    let mut attempts = 0;
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .timeout(Duration::from_secs(60))
+        .build();
+    let result: Result<(), ()> =
+        retry_with_overall_timeout(&mut waiter, Duration::from_millis(50), || {
+            attempts += 1;
+            std::thread::sleep(Duration::from_millis(20));
+            Err(())
+        });
+    assert_eq!(result, Err(()));
+    assert!(attempts <= 3, "{} attempts", attempts);
+
+    // The waiter doesn't wait past the timeout either.
+    attempts = 0;
+    let mut waiter = Delay::throttle(Duration::from_secs(60));
+    let result: Result<(), ()> =
+        retry_with_overall_timeout(&mut waiter, Duration::from_secs(1), || {
+            attempts += 1;
+            Err(())
+        });
+    assert_eq!(result, Err(()));
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn retry_budget_limits_retries_across_calls() {
+    use crate::{retry_with_budget, BudgetError, MockClock, RetryBudget};
+
+    let clock = MockClock::new();
+    let budget = RetryBudget::new(0.5)
+        .with_min_per_second(0.0)
+        .with_clock(clock.shared());
+    let mut waiter = Delay::count_timeout(10);
+    let mut attempts = 0;
+    let mut call = |budget: &RetryBudget| {
+        retry_with_budget(&mut waiter, budget, || -> Result<(), ()> {
+            attempts += 1;
+            Err(())
+        })
+    };
+
+    // Half a retry per call: the first call can't retry, the second one can once.
+    assert_eq!(call(&budget), Err(BudgetError::Exhausted(())));
+    assert_eq!(call(&budget), Err(BudgetError::Exhausted(())));
+    assert_eq!(attempts, 3);
+
+    // The budget also refills over time, up to its maximum.
+    let budget = budget.with_min_per_second(1.0).with_max_balance(2.0);
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(budget.available(), 2);
+}
+
+#[test]
+fn circuit_breaker_opens_and_closes() {
+    use crate::{CircuitBreaker, CircuitError, CircuitState, MockClock};
+
+    let clock = MockClock::new();
+    let breaker = CircuitBreaker::new(2, Delay::exponential_backoff(Duration::from_secs(1), 2.0))
+        .with_clock(clock.shared());
+    let fail = || Err::<(), _>("down");
+
+    assert_eq!(breaker.call(fail), Err(CircuitError::Failed("down")));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(breaker.call(fail), Err(CircuitError::Failed("down")));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert_eq!(breaker.call(|| Ok::<_, ()>(1)), Err(CircuitError::Open));
+
+    // The trial call fails, so the circuit opens again for longer.
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    assert_eq!(breaker.call(fail), Err(CircuitError::Failed("down")));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.call(|| Ok::<_, ()>(1)), Ok(1));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+
+    // Retries stop as soon as the circuit opens.
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(10)
+        .build();
+    let mut attempts = 0;
+    let result = crate::retry_with_breaker(&mut waiter, &breaker, || {
+        attempts += 1;
+        Err::<(), _>("down")
+    });
+    assert_eq!(result, Err(CircuitError::Open));
+    assert_eq!(attempts, 2);
+
+    // An open duration too long for an Instant keeps the circuit open.
+    let breaker = CircuitBreaker::new(1, crate::ThrottleWaiter::from_rate(0.0));
+    assert_eq!(breaker.call(fail), Err(CircuitError::Failed("down")));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert_eq!(breaker.call(|| Ok::<_, ()>(1)), Err(CircuitError::Open));
+}
+
+#[test]
+fn bulkhead_limits_concurrency() {
+    use crate::{Bulkhead, MockClock, WaiterError, WaiterExt};
+
+    let bulkhead = Bulkhead::new(2);
+    let first = bulkhead.try_acquire().unwrap();
+    let other = bulkhead.clone();
+    let _second = other.try_acquire().unwrap();
+    assert!(other.try_acquire().is_none());
+    assert_eq!(bulkhead.available(), 0);
+
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(10))
+        .max_attempts(3)
+        .build()
+        .with_clock(MockClock::new().shared());
+    assert_eq!(
+        bulkhead.acquire(&mut waiter).err(),
+        Some(WaiterError::Timeout {
+            elapsed: Duration::from_secs(0),
+            attempts: 3,
+        })
+    );
+
+    drop(first);
+    assert_eq!(bulkhead.available(), 1);
+    assert!(bulkhead.acquire(&mut waiter).is_ok());
+}
+
+#[tokio::test]
+async fn hedge_returns_the_first_attempt_to_complete() {
+    let attempts = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(20))
+        .max_attempts(2)
+        .build();
+
+    let result = crate::hedge(&mut waiter, || {
+        let attempt = attempts.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        async move {
+            // The first attempt is stuck, the second one responds quickly.
+            let delay = if attempt == 0 { 5000 } else { 1 };
+            let mut sleep = Delay::throttle(Duration::from_millis(delay));
+            sleep.start();
+            sleep.async_wait().await.unwrap();
+            attempt
+        }
+    })
+    .await;
+    assert_eq!(result, 1);
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::Relaxed), 2);
+}
+
+#[test]
+fn retry_after_hint_overrides_next_delay() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::exponential_backoff(Duration::from_secs(1), 2.0)
+        .with_retry_after()
+        .with_clock(clock.shared());
+    let hint = waiter.hint();
+
+    let mut attempts = 0;
+    let result: Result<(), ()> = crate::retry(&mut waiter, || {
+        attempts += 1;
+        if attempts == 2 {
+            hint.hint(Duration::from_secs(30));
+        }
+        if attempts < 4 {
+            Err(())
+        } else {
+            Ok(())
+        }
+    });
+    assert!(result.is_ok());
+    // 1s, then the 30s hint instead of 2s, then back to the backoff with 4s.
+    assert_eq!(clock.elapsed(), Duration::from_secs(35));
+}
+
+#[cfg(feature = "http-hints")]
+#[test]
+fn rate_limit_headers_pace_waits() {
+    use crate::{MockClock, RateLimitHeaders, RateLimitWaiter, WaiterExt};
+
+    let headers = RateLimitHeaders::from_headers(vec![
+        ("Content-Type", "text/plain"),
+        ("RateLimit-Limit", "100, 100;w=60"),
+        ("ratelimit-remaining", "2"),
+        ("RateLimit-Reset", "10"),
+    ])
+    .unwrap();
+    assert_eq!(headers.limit, 100);
+    assert_eq!(headers.remaining, 2);
+    assert_eq!(headers.reset, Duration::from_secs(10));
+    assert_eq!(RateLimitHeaders::parse("100", "x", "10"), None);
+
+    let clock = MockClock::new();
+    let mut waiter = RateLimitWaiter::new().with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(0));
+
+    waiter.update(&headers);
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(5));
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(10));
+
+    // A reset too far for an Instant waits as long as it can instead of panicking.
+    let headers = RateLimitHeaders::parse("100", "0", "18446744073709551615").unwrap();
+    waiter.update(&headers);
+    assert!(waiter.advance().unwrap() > Duration::from_secs(3600 * 24 * 365));
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn delay_layer_delays_requests() {
+    use crate::{DelayError, DelayLayer, MockClock, WaiterError, WaiterExt};
+    use std::task::{Context, Poll};
+    use tower_crate::{Layer, Service};
+
+    #[derive(Clone)]
+    struct Echo;
+    impl Service<u32> for Echo {
+        type Response = u32;
+        type Error = ();
+        type Future = futures_util::future::Ready<Result<u32, ()>>;
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, request: u32) -> Self::Future {
+            futures_util::future::ok(request)
+        }
+    }
+
+    let clock = MockClock::new();
+    let waiter = Delay::builder()
+        .throttle(Duration::from_secs(1))
+        .max_attempts(2)
+        .build()
+        .with_clock(clock.shared());
+    let mut service = DelayLayer::new(waiter).layer(Echo);
+    assert_eq!(service.call(1).await, Ok(1));
+    assert_eq!(service.call(2).await, Ok(2));
+    assert_eq!(clock.elapsed(), Duration::from_secs(2));
+    assert_eq!(
+        service.call(3).await,
+        Err(DelayError::Waiter(WaiterError::Timeout {
+            elapsed: Duration::from_secs(0),
+            attempts: 2,
+        }))
+    );
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn retry_policy_follows_waiter() {
+    use crate::{MockClock, RetryDecision, RetryPolicy};
+    use tower_crate::retry::Policy;
+
+    let clock = MockClock::new();
+    let waiter = Delay::builder()
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .max_attempts(2)
+        .build();
+    let policy = RetryPolicy::new(waiter, |e: &&str| {
+        if *e == "fatal" {
+            RetryDecision::Abort
+        } else {
+            RetryDecision::Retry
+        }
+    })
+    .with_clock(clock.shared());
+
+    let request = 1u32;
+    assert!(Policy::<u32, (), &str>::retry(&policy, &request, Ok(&())).is_none());
+    assert!(Policy::<u32, (), &str>::retry(&policy, &request, Err(&"fatal")).is_none());
+
+    let policy = Policy::<u32, (), &str>::retry(&policy, &request, Err(&"busy"))
+        .unwrap()
+        .await;
+    let policy = Policy::<u32, (), &str>::retry(&policy, &request, Err(&"busy"))
+        .unwrap()
+        .await;
+    assert_eq!(clock.elapsed(), Duration::from_secs(3));
+    assert!(Policy::<u32, (), &str>::retry(&policy, &request, Err(&"busy")).is_none());
+    assert_eq!(
+        Policy::<u32, (), &str>::clone_request(&policy, &request),
+        Some(1)
+    );
+}
+
+#[tokio::test]
+async fn streams_can_be_throttled() {
+    use crate::{MockClock, WaiterExt, WaiterStreamExt};
+    use futures_util::stream::{self, StreamExt};
+
+    let clock = MockClock::new();
+    let waiter = Delay::throttle(Duration::from_secs(1)).with_clock(clock.shared());
+    let mut items = stream::iter(vec![1, 2, 3]).throttle_with(waiter);
+    let mut collected = Vec::new();
+    while let Some(item) = items.next().await {
+        collected.push(item);
+    }
+    assert_eq!(collected, vec![1, 2, 3]);
+    // The end of the stream is only known after a last wait.
+    assert_eq!(clock.elapsed(), Duration::from_secs(4));
+
+    let waiter = Delay::builder().max_attempts(2).build();
+    let mut items = stream::iter(vec![1, 2, 3]).throttle_with(waiter);
+    assert_eq!(items.next().await, Some(1));
+    assert_eq!(items.next().await, Some(2));
+    assert_eq!(items.next().await, None);
+}
+
+#[tokio::test]
+async fn throttled_streams_wait_once_for_pending_items() {
+    use crate::{FixedScheduleWaiter, MockClock, WaiterExt, WaiterStreamExt};
+    use futures_util::stream::{self, StreamExt};
+    use std::task::Poll;
+
+    // Each item is pending once before it is ready.
+    let mut polls = 0;
+    let inner = stream::poll_fn(move |cx| {
+        polls += 1;
+        if polls % 2 == 1 {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        } else {
+            Poll::Ready(if polls < 6 { Some(polls / 2) } else { None })
+        }
+    });
+    let clock = MockClock::new();
+    let waiter =
+        FixedScheduleWaiter::new(vec![Duration::from_secs(1); 3]).with_clock(clock.shared());
+    let items: Vec<_> = inner.throttle_with(waiter).collect().await;
+    assert_eq!(items, vec![1, 2]);
+    assert_eq!(clock.elapsed(), Duration::from_secs(3));
+}
+
+#[test]
+fn iterators_can_be_retried_per_item() {
+    use crate::RetryIteratorExt;
+
+    let mut calls = 0;
+    let results: Vec<Result<u32, u32>> = vec![1, 2, 3]
+        .into_iter()
+        .map_retry(Delay::count_timeout(3), |item| {
+            calls += 1;
+            // Item 2 succeeds on its second attempt, item 3 never does.
+            match *item {
+                2 if calls == 2 => Err(*item),
+                3 => Err(*item),
+                _ => Ok(*item * 10),
+            }
+        })
+        .collect();
+    assert_eq!(results, vec![Ok(10), Ok(20), Err(3)]);
+    // The waiter allows 3 waits, so 4 attempts, for each item.
+    assert_eq!(calls, 1 + 2 + 4);
+}
+
+#[test]
+fn interval_does_not_drift() {
+    use crate::{Interval, MissedTickBehavior, MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut interval = Delay::interval(Duration::from_secs(1)).with_clock(clock.shared());
+    interval.start();
+    for _ in 0..3 {
+        // Work takes some of the period.
+        clock.advance(Duration::from_millis(300));
+        interval.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_secs(3));
+
+    let missed = |behavior| {
+        let clock = MockClock::new();
+        let mut interval = Interval::new(Duration::from_secs(1))
+            .with_missed_tick_behavior(behavior)
+            .with_clock(clock.shared());
+        interval.start();
+        clock.advance(Duration::from_millis(2500));
+        interval.wait().unwrap();
+        interval.wait().unwrap();
+        clock.elapsed()
+    };
+    assert_eq!(
+        missed(MissedTickBehavior::Burst),
+        Duration::from_millis(2500)
+    );
+    assert_eq!(
+        missed(MissedTickBehavior::Delay),
+        Duration::from_millis(3500)
+    );
+    assert_eq!(
+        missed(MissedTickBehavior::Skip),
+        Duration::from_millis(4000)
+    );
+}
+
+#[test]
+fn delay_queue_pops_due_items_in_order() {
+    use crate::{DelayQueue, MockClock};
+
+    let clock = MockClock::new();
+    let queue = DelayQueue::new().with_clock(clock.shared());
+    queue.insert("c", Duration::from_secs(3));
+    queue.insert("a", Duration::from_secs(1));
+    let mut backoff = Delay::exponential_backoff(Duration::from_secs(2), 2.0);
+    backoff.start();
+    queue.insert_with("b", &mut backoff).unwrap();
+    assert_eq!(queue.len(), 3);
+
+    assert_eq!(queue.try_pop(), None);
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(queue.try_pop(), Some("a"));
+    assert_eq!(queue.pop(), "b");
+    assert_eq!(queue.try_pop(), None);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(queue.clone().try_pop(), Some("c"));
+    assert!(queue.is_empty());
+
+    // A delay too long for an Instant is never due.
+    queue.insert("never", Duration::from_secs(u64::MAX));
+    assert_eq!(queue.try_pop(), None);
+    assert_eq!(queue.len(), 1);
+}
+
+#[tokio::test]
+async fn delay_queue_is_a_stream() {
+    use crate::DelayQueue;
+    use futures_util::stream::StreamExt;
+
+    let mut queue = DelayQueue::new();
+    let producer = queue.clone();
+    producer.insert(2, Duration::from_millis(20));
+    producer.insert(1, Duration::from_millis(10));
+    assert_eq!(queue.next().await, Some(1));
+    assert_eq!(queue.next().await, Some(2));
+}
+
+#[test]
+fn delay_queue_streams_keep_one_waker() {
+    use crate::DelayQueue;
+    use futures_util::stream::Stream;
+    use futures_util::task::{waker, ArcWake};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    struct CountWakes(AtomicUsize);
+    impl ArcWake for CountWakes {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+    let waker = waker(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut queue = DelayQueue::<u32>::new();
+    for _ in 0..10 {
+        assert_eq!(Pin::new(&mut queue).poll_next(&mut cx), Poll::Pending);
+    }
+    queue.insert(1, Duration::from_secs(60));
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn debouncer_fires_after_quiet_period() {
+    use crate::{Debouncer, MockClock};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let clock = MockClock::new();
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    let debouncer = Debouncer::new(Duration::from_secs(1))
+        .with_clock(clock.shared())
+        .on_fire(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+    assert!(!debouncer.wait());
+    for _ in 0..5 {
+        assert!(!debouncer.trigger());
+        clock.advance(Duration::from_millis(500));
+        assert!(!debouncer.poll());
+    }
+    assert_eq!(fired.load(Ordering::SeqCst), 0);
+    assert!(debouncer.wait());
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+    let leading = Debouncer::new(Duration::from_secs(1))
+        .with_clock(clock.shared())
+        .leading(true);
+    assert!(leading.trigger());
+    clock.advance(Duration::from_secs(2));
+    assert!(!leading.poll());
+    assert!(leading.trigger());
+    assert!(!leading.trigger());
+    assert!(leading.wait());
+
+    // A quiet period too long for an Instant never fires.
+    let forever =
+        Debouncer::with_waiter(crate::ThrottleWaiter::from_rate(0.0)).with_clock(clock.shared());
+    assert!(!forever.trigger());
+    clock.advance(Duration::from_secs(3600));
+    assert!(!forever.poll());
+}
+
+#[test]
+fn coalescer_batches_requests_within_window() {
+    use crate::Coalescer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let coalescer = Coalescer::new(Duration::from_millis(50));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let coalescer = coalescer.clone();
+            let calls = calls.clone();
+            std::thread::spawn(move || {
+                coalescer.get("key", |key| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    key.len()
+                })
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 3);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(coalescer.pending(), 0);
+}
+
+#[tokio::test]
+async fn coalescer_batches_async_requests() {
+    use crate::Coalescer;
+    use futures_util::future::join;
+
+    let coalescer = Coalescer::new(Duration::from_millis(10));
+    let (a, b) = join(
+        coalescer.get_async(1, |key| async move { key * 10 }),
+        coalescer.get_async(1, |_| async { unreachable!() }),
+    )
+    .await;
+    assert_eq!((a, b), (10, 10));
+}
+
+#[test]
+fn precision_waiter_waits_exact_delay() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_millis(10))
+        .precise()
+        .with_margin(Duration::from_millis(3))
+        .with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(10));
+
+    let mut waiter = Delay::throttle(Duration::from_millis(5)).precise();
+    waiter.start();
+    let start = std::time::Instant::now();
+    waiter.wait().unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(5));
+}
+
+#[tokio::test]
+async fn precision_waiter_takes_delays_too_long_for_an_instant() {
+    use crate::WaiterExt;
+
+    let mut waiter = crate::ThrottleWaiter::from_rate(0.0).precise();
+    waiter.start();
+    drop(waiter.async_wait());
+}
+
+#[test]
+fn retry_after_hint_interrupts_blocking_wait() {
+    use crate::WaiterExt;
+
+    let mut waiter = Delay::throttle(Duration::from_secs(30)).with_retry_after();
+    let hint = waiter.hint();
+    waiter.start();
+    let start = std::time::Instant::now();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        hint.hint(Duration::from_millis(10));
+    });
+    waiter.wait().unwrap();
+    handle.join().unwrap();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(30));
+    assert!(elapsed < Duration::from_secs(10));
+}
+
+#[test]
+fn wait_with_custom_sleep() {
+    use crate::WaiterExt;
+
+    let mut slept = Vec::new();
+    let mut waiter = Delay::exponential_backoff(Duration::from_millis(10), 2.0);
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait_with(&mut |d| slept.push(d)).unwrap();
+    }
+    assert_eq!(
+        slept,
+        vec![
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(40)
+        ]
+    );
+}
+
+#[test]
+fn exponential_backoff_cap_behavior() {
+    use crate::{CapBehavior, ExponentialBackoffWaiter, WaiterError};
+
+    let delays = |behavior| {
+        let mut waiter =
+            ExponentialBackoffWaiter::new(Duration::from_secs(1), 1e30, Duration::from_secs(60))
+                .with_cap_behavior(behavior);
+        waiter.start();
+        (0..3).map(|_| waiter.advance()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        delays(CapBehavior::Saturate),
+        vec![
+            Ok(Duration::from_secs(1)),
+            Ok(Duration::from_secs(60)),
+            Ok(Duration::from_secs(60))
+        ]
+    );
+    assert_eq!(
+        delays(CapBehavior::Reset),
+        vec![
+            Ok(Duration::from_secs(1)),
+            Ok(Duration::from_secs(1)),
+            Ok(Duration::from_secs(1))
+        ]
+    );
+    assert_eq!(
+        delays(CapBehavior::Timeout),
+        vec![
+            Ok(Duration::from_secs(1)),
+            Err(WaiterError::Timeout {
+                elapsed: Duration::from_secs(0),
+                attempts: 1,
+            }),
+            Err(WaiterError::Timeout {
+                elapsed: Duration::from_secs(0),
+                attempts: 1,
+            })
+        ]
+    );
+
+    let mut uncapped = Delay::exponential_backoff(Duration::from_secs(1), 1e30);
+    uncapped.start();
+    uncapped.advance().unwrap();
+    assert_eq!(uncapped.advance(), Ok(Duration::from_nanos(u64::MAX)));
+}
+
+#[test]
+fn exponential_backoff_ratio_is_exact() {
+    let mut waiter =
+        Delay::exponential_backoff_ratio(Duration::from_millis(100), 3, 2, Duration::from_secs(1));
+    waiter.start();
+    let delays: Vec<_> = (0..6).map(|_| waiter.advance().unwrap()).collect();
+    assert_eq!(
+        delays,
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(150),
+            Duration::from_millis(225),
+            Duration::from_micros(337_500),
+            Duration::from_micros(506_250),
+            Duration::from_micros(759_375),
+        ]
+    );
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(1)));
+}
+
+#[test]
+fn exponential_backoff_adds_an_increment() {
+    use crate::ExponentialBackoffWaiter;
+
+    let mut waiter = ExponentialBackoffWaiter::with_ratio(
+        Duration::from_millis(100),
+        2,
+        1,
+        Duration::from_secs(1),
+    )
+    .with_increment(Duration::from_millis(100));
+    waiter.start();
+    let millis: Vec<_> = (0..5)
+        .map(|_| waiter.advance().unwrap().as_millis())
+        .collect();
+    assert_eq!(millis, vec![100, 300, 700, 1000, 1000]);
+    assert_eq!(waiter.to_string(), "exponential(100ms x2/1 +100ms ≤ 1s)");
+
+    // Shrinking undoes the increment too.
+    waiter.report_success();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_millis(450)));
+}
+
+#[test]
+fn timeouts_report_the_time_remaining() {
+    use crate::{MaxElapsedWaiter, MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::builder()
+        .timeout(Duration::from_secs(10))
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .build()
+        .with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    waiter.wait().unwrap();
+    assert_eq!(waiter.remaining(), Some(Duration::from_secs(7)));
+
+    // The earliest limit wins.
+    let mut waiter =
+        MaxElapsedWaiter::new(waiter, Duration::from_secs(5)).with_clock(clock.shared());
+    waiter.start();
+    assert_eq!(waiter.remaining(), Some(Duration::from_secs(5)));
+    clock.advance(Duration::from_secs(8));
+    assert_eq!(waiter.remaining(), Some(Duration::from_secs(0)));
+
+    assert_eq!(Delay::throttle(Duration::from_secs(1)).remaining(), None);
+}
+
+#[test]
+fn max_elapsed_wraps_any_waiter() {
+    use crate::{MockClock, WaiterError, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(1))
+        .max_elapsed(Duration::from_millis(2500))
+        .with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..2 {
+        waiter.wait().unwrap();
+    }
+    // The last wait is cut short to end with the budget.
+    assert_eq!(waiter.next_delay(), Some(Duration::from_millis(500)));
+    waiter.wait().unwrap();
+    assert_eq!(waiter.elapsed(), Duration::from_millis(2500));
+    assert_eq!(waiter.next_delay(), None);
+    assert_eq!(
+        waiter.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_millis(2500),
+            attempts: 3,
+        })
+    );
+
+    waiter.restart().unwrap();
+    assert_eq!(waiter.wait(), Ok(()));
+}
+
+#[test]
+fn random_waiter_stays_in_range() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::random(Duration::from_millis(100), Duration::from_millis(200))
+        .with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..100 {
+        let delay = waiter.advance().unwrap();
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+    waiter.wait().unwrap();
+    assert!(clock.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn seeded_rng_reproduces_random_delays() {
+    use crate::{DecorrelatedJitterWaiter, Jitter, JitterWaiter, RandomWaiter, SeededRng};
+
+    fn delays<W: Waiter>(mut waiter: W) -> Vec<Duration> {
+        waiter.start();
+        (0..10).map(|_| waiter.advance().unwrap()).collect()
+    }
+    let random = || {
+        RandomWaiter::new(Duration::from_millis(1), Duration::from_secs(1))
+            .with_rng(SeededRng::new(42))
+    };
+    assert_eq!(delays(random()), delays(random()));
+    assert_ne!(
+        delays(random()),
+        delays(random().with_rng(SeededRng::new(43)))
+    );
+
+    let jitter = || {
+        JitterWaiter::new(Delay::throttle(Duration::from_secs(1)), Jitter::Full)
+            .with_rng(SeededRng::new(7))
+    };
+    assert_eq!(delays(jitter()), delays(jitter()));
+
+    let decorrelated = || {
+        DecorrelatedJitterWaiter::new(Duration::from_millis(10), Duration::from_secs(10))
+            .with_rng(SeededRng::new(7))
+    };
+    assert_eq!(delays(decorrelated()), delays(decorrelated()));
+}
+
+#[test]
+fn waiter_error_is_an_error() {
+    fn fails() -> Result<(), Box<dyn std::error::Error>> {
+        let mut waiter = Delay::count_timeout(1);
+        waiter.start();
+        waiter.wait()?;
+        waiter.wait()?;
+        Ok(())
+    }
+    assert_eq!(
+        fails().unwrap_err().to_string(),
+        "the waiter timed out after 0ns and 1 waits"
+    );
+    assert_eq!(
+        crate::WaiterError::NotStarted.to_string(),
+        "the waiter was not started"
+    );
+}
+
+#[test]
+fn timeout_reports_progress() {
+    use crate::{MockClock, WaiterError, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::builder()
+        .timeout(Duration::from_secs(5))
+        .fixed_schedule(vec![Duration::from_secs(2); 10])
+        .build()
+        .with_clock(clock.shared());
+    waiter.start();
+    let error = loop {
+        if let Err(e) = waiter.wait() {
+            break e;
+        }
+    };
+    assert_eq!(
+        error,
+        WaiterError::Timeout {
+            elapsed: Duration::from_secs(6),
+            attempts: 3,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "the waiter timed out after 6s and 3 waits"
+    );
+}
+
+#[test]
+fn retry_with_result_keeps_last_error() {
+    use crate::{retry_with_result, MockClock, RetryError, WaiterExt};
+
+    let mut waiter = Delay::builder()
+        .exponential_backoff(Duration::from_secs(1), 2.0)
+        .max_attempts(3)
+        .build()
+        .with_clock(MockClock::new().shared());
+    let mut calls = 0;
+    let result: Result<(), _> = retry_with_result(&mut waiter, || {
+        calls += 1;
+        Err(format!("failure {}", calls))
+    });
+    let error = result.unwrap_err();
+    assert_eq!(
+        error,
+        RetryError {
+            source: "failure 4".to_string(),
+            attempts: 4,
+            total_delay: Duration::from_secs(7),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "failure 4 (after 4 attempts and 7s of delays)"
+    );
+}
+
+#[tokio::test]
+async fn retry_async_with_result_keeps_last_error() {
+    use crate::retry_async_with_result;
+
+    let mut waiter = Delay::count_timeout(2);
+    let error = retry_async_with_result(&mut waiter, || async { Err::<(), _>("nope") })
+        .await
+        .unwrap_err();
+    assert_eq!((error.source, error.attempts), ("nope", 3));
+}
+
+#[tokio::test]
+async fn async_waits_can_be_spawned() {
+    use crate::{retry_async, WaiterExt};
+
+    let mut waiter = Delay::builder()
+        .throttle(Duration::from_millis(1))
+        .max_attempts(2)
+        .build()
+        .cancelable()
+        .observed();
+    let task = tokio::spawn(async move {
+        waiter.start();
+        waiter.async_wait().await.unwrap();
+        let mut calls = 0;
+        retry_async(&mut waiter, || {
+            calls += 1;
+            async move {
+                if calls < 3 {
+                    Err(())
+                } else {
+                    Ok(calls)
+                }
+            }
+        })
+        .await
+    });
+    assert_eq!(task.await.unwrap(), Ok(3));
+}
+
+#[test]
+fn shared_backoff_slows_down_all_workers() {
+    use crate::{ExponentialBackoffWaiter, MockClock, SharedBackoff, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut first = SharedBackoff::new(ExponentialBackoffWaiter::new(
+        Duration::from_millis(10),
+        2.0,
+        Duration::from_secs(1),
+    ))
+    .with_clock(clock.shared());
+    let mut second = first.clone();
+    first.start();
+    second.start();
+
+    first.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(10));
+    assert_eq!(second.advance(), Ok(Duration::from_millis(20)));
+    assert_eq!(first.remaining(), Duration::from_millis(20));
+    assert_eq!(first.advance(), Ok(Duration::from_millis(40)));
+    assert_eq!(second.attempts(), first.attempts());
+
+    second.report_success();
+    assert_eq!(first.remaining(), Duration::from_secs(0));
+    first.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_millis(20));
+
+    // A delay too long for an Instant is waited as long as possible.
+    let mut forever = SharedBackoff::new(crate::ThrottleWaiter::from_rate(0.0));
+    forever.start();
+    assert!(forever.advance().unwrap() > Duration::from_secs(3600 * 24 * 365));
+}
+
+#[test]
+fn backoff_map_keeps_a_waiter_per_key() {
+    use crate::{BackoffMap, ExponentialBackoffWaiter, MockClock};
+
+    let clock = MockClock::new();
+    let map = BackoffMap::new(|_: &&str| {
+        ExponentialBackoffWaiter::new(Duration::from_secs(1), 2.0, Duration::from_secs(60))
+    })
+    .with_idle_timeout(Duration::from_secs(30))
+    .with_clock(clock.shared());
+
+    map.wait(&"a").unwrap();
+    map.wait(&"a").unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(3));
+    assert_eq!(map.next_delay(&"a"), Some(Duration::from_secs(4)));
+    assert_eq!(map.next_delay(&"b"), Some(Duration::from_secs(1)));
+    map.wait(&"b").unwrap();
+    assert_eq!(map.len(), 2);
+
+    map.reset(&"b");
+    assert_eq!(map.len(), 1);
+    clock.advance(Duration::from_secs(31));
+    map.evict_idle();
+    assert!(map.is_empty());
+    map.wait(&"a").unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(36));
+}
+
+#[test]
+fn exponential_backoff_decays_when_quiet() {
+    use crate::{ExponentialBackoffWaiter, MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter =
+        ExponentialBackoffWaiter::new(Duration::from_secs(1), 2.0, Duration::from_secs(60))
+            .with_decay(Duration::from_secs(10))
+            .with_clock(clock.shared());
+    waiter.start();
+    for _ in 0..4 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(16)));
+
+    waiter.report_success();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(8)));
+    clock.advance(Duration::from_secs(9));
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(8)));
+
+    clock.advance(Duration::from_secs(8 + 25));
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(4)));
+    clock.advance(Duration::from_secs(4 + 1000));
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(1)));
+    for _ in 0..10 {
+        waiter.report_success();
+    }
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn gradient_limit_follows_the_latency() {
+    use crate::GradientWaiter;
+
+    let mut waiter = GradientWaiter::new(4, 20);
+    let recorder = waiter.clone();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(0)));
+
+    // A stable latency grows the limit up to the max.
+    for _ in 0..30 {
+        recorder.record(Duration::from_millis(100));
+    }
+    assert_eq!(waiter.limit(), 20);
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(5)));
+
+    // Once the latency rises, the limit shrinks.
+    for _ in 0..30 {
+        recorder.record(Duration::from_millis(400));
+    }
+    assert!(waiter.limit() <= 12, "limit is {}", waiter.limit());
+}
+
+#[test]
+fn latency_throttle_follows_the_average_latency() {
+    use crate::LatencyThrottleWaiter;
+
+    let mut waiter = LatencyThrottleWaiter::new(2.0, Duration::from_secs(1))
+        .with_smoothing(0.5)
+        .with_bounds(Duration::from_millis(10), Duration::from_secs(5));
+    let recorder = waiter.clone();
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(1)));
+
+    recorder.record_latency(Duration::from_millis(100));
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(200)));
+    recorder.record_latency(Duration::from_millis(300));
+    assert_eq!(recorder.average_latency(), Some(Duration::from_millis(200)));
+    assert_eq!(waiter.advance(), Ok(Duration::from_millis(400)));
+
+    // The waits stay within the bounds.
+    recorder.record_latency(Duration::from_secs(60));
+    assert_eq!(waiter.advance(), Ok(Duration::from_secs(5)));
+}
+
+#[test]
+fn warmup_shortens_waits_over_the_window() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::warmup(
+        Duration::from_secs(10),
+        Duration::from_secs(1),
+        Duration::from_secs(90),
+    )
+    .with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(10));
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(9)));
+    waiter.wait().unwrap();
+    clock.advance(Duration::from_secs(51));
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(3)));
+    clock.advance(Duration::from_secs(60));
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert_eq!(clock.elapsed(), Duration::from_secs(133));
+}
+
+#[test]
+fn logarithmic_and_harmonic_backoffs_grow_slowly() {
+    let millis = |mut waiter: Delay| {
+        waiter.start();
+        (0..4)
+            .map(|_| waiter.advance().unwrap().as_millis())
+            .collect::<Vec<_>>()
+    };
+    // 1 + ln(n) is 1, 1.693, 2.099 and 2.386.
+    assert_eq!(
+        millis(Delay::logarithmic_backoff(
+            Duration::from_secs(1),
+            Duration::from_secs(60)
+        )),
+        vec![1000, 1693, 2098, 2386]
+    );
+    assert_eq!(
+        millis(Delay::harmonic_backoff(
+            Duration::from_secs(6),
+            Duration::from_secs(10)
+        )),
+        vec![6000, 9000, 10000, 10000]
+    );
+}
+
+#[test]
+fn fn_backoff_grows_with_the_function() {
+    use crate::FnBackoffWaiter;
+
+    // Grows by the number of waits so far, in seconds.
+    let mut waiter = FnBackoffWaiter::new(Duration::from_secs(1), |previous, attempts| {
+        previous + Duration::from_secs(attempts as u64)
+    })
+    .with_cap(Duration::from_secs(8));
+    waiter.start();
+    let secs: Vec<u64> = (0..5)
+        .map(|_| waiter.advance().unwrap().as_secs())
+        .collect();
+    assert_eq!(secs, vec![1, 2, 4, 7, 8]);
+    assert_eq!(waiter.to_string(), "fn_backoff(1s ≤ 8s)");
+
+    waiter.restart().unwrap();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(1)));
+}
+
+#[cfg(feature = "cron")]
+#[test]
+fn cron_waits_until_the_next_occurrence() {
+    use crate::{CronWaiter, MockClock, WaiterExt};
+
+    // The wall clock of the mock starts at midnight UTC.
+    let clock = MockClock::new();
+    let mut waiter = CronWaiter::parse("0 0 3 * * *")
+        .unwrap()
+        .with_clock(clock.shared());
+    waiter.start();
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(3 * 3600));
+    clock.advance(Duration::from_secs(600));
+    assert_eq!(
+        waiter.next_delay(),
+        Some(Duration::from_secs(24 * 3600 - 600))
+    );
+    assert!(CronWaiter::parse("not a schedule").is_err());
+}
+
+#[test]
+fn system_time_waiter_follows_clock_adjustments() {
+    use crate::{MockClock, SystemTimeWaiter, WaiterExt};
+    use std::time::UNIX_EPOCH;
+
+    let clock = MockClock::new();
+    let mut waiter = SystemTimeWaiter::new(UNIX_EPOCH + Duration::from_secs(100))
+        .with_recheck(Duration::from_secs(30))
+        .with_clock(clock.shared());
+    waiter.start();
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(100)));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(100));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(100));
+
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(60));
+    waiter.wait().unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(140));
+    assert_eq!(waiter.attempts(), 3);
+}
+
+#[test]
+fn timeouts_can_use_the_wall_clock() {
+    use crate::{DeadlineWaiter, MockClock, TimeSource, TimeoutWaiter, WaiterError, WaiterExt};
+    use std::time::UNIX_EPOCH;
+
+    // The machine sleeps for an hour: the wall clock jumps, the monotonic clock doesn't.
+    let clock = MockClock::new();
+    let mut monotonic = TimeoutWaiter::new(Duration::from_secs(60)).with_clock(clock.shared());
+    let mut wall = TimeoutWaiter::new(Duration::from_secs(60))
+        .with_time_source(TimeSource::WallClock)
+        .with_clock(clock.shared());
+    let mut deadline = DeadlineWaiter::at_system_time(UNIX_EPOCH + Duration::from_secs(60))
+        .with_clock(clock.shared());
+    monotonic.start();
+    wall.start();
+    deadline.start();
+    clock.set_system_time(UNIX_EPOCH + Duration::from_secs(3600));
+
+    assert_eq!(monotonic.wait(), Ok(()));
+    assert_eq!(
+        wall.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_secs(3600),
+            attempts: 0,
+        })
+    );
+    assert!(deadline.wait().is_err());
+}
+
+#[test]
+fn pause_blocks_the_next_waits_until_resumed() {
+    use crate::{MockClock, WaiterExt};
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(1))
+        .with_clock(clock.shared())
+        .pausable();
+    let handle = waiter.handle();
+    waiter.start();
+    waiter.wait().unwrap();
+
+    handle.pause();
+    assert!(handle.is_paused());
+    let start = Instant::now();
+    let resumer = handle.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        resumer.resume();
+    });
+    waiter.wait().unwrap();
+    assert!(Instant::now().duration_since(start) >= Duration::from_millis(50));
+    assert!(!handle.is_paused());
+    assert_eq!(clock.elapsed(), Duration::from_secs(2));
+
+    // Paused waits sleep with the clock of the waiter, which time passes on while paused.
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(1))
+        .pausable()
+        .with_clock(clock.shared());
+    let handle = waiter.handle();
+    waiter.start();
+    handle.pause();
+    let resumer = handle.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        resumer.resume();
+    });
+    waiter.wait().unwrap();
+    assert!(clock.elapsed() > Duration::from_secs(3600));
+}
+
+#[tokio::test]
+async fn wrapped_pausable_waiters_dont_block_async_waits() {
+    use crate::WaiterExt;
+
+    let waiter = Delay::throttle(Duration::from_secs(0)).pausable();
+    let handle = waiter.handle();
+    let mut waiter = waiter.with_stats();
+    waiter.start();
+
+    handle.pause();
+    let start = Instant::now();
+    let resumer = handle.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        resumer.resume();
+    });
+    waiter.async_wait().await.unwrap();
+    assert!(Instant::now().duration_since(start) < Duration::from_millis(200));
+}
+
+#[test]
+fn max_elapsed_reports_progress() {
+    use crate::{MockClock, Progress};
+    use std::sync::{Arc, Mutex};
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let recorded = reports.clone();
+    let mut waiter = Delay::builder()
+        .fibonacci_backoff(Duration::from_secs(5), Duration::from_secs(60))
+        .max_elapsed(Duration::from_secs(12))
+        .on_progress(move |progress| recorded.lock().unwrap().push(*progress))
+        .clock(MockClock::new().shared())
+        .build();
+    waiter.start();
+    while waiter.wait().is_ok() {}
+
+    let progress = |elapsed, remaining, next_delay| Progress {
+        elapsed: Duration::from_secs(elapsed),
+        remaining: Duration::from_secs(remaining),
+        next_delay: Duration::from_secs(next_delay),
+    };
+    assert_eq!(
+        *reports.lock().unwrap(),
+        vec![progress(0, 12, 5), progress(5, 7, 5), progress(10, 2, 2)]
+    );
+}
+
+#[test]
+fn waiters_describe_their_policy() {
+    use crate::{ExponentialBackoffWaiter, Jitter, JitterWaiter, MaxAttemptsWaiter, WaiterExt};
+
+    let backoff = MaxAttemptsWaiter::new(
+        ExponentialBackoffWaiter::new(Duration::from_millis(500), 2.0, Duration::from_secs(30)),
+        10,
+    );
+    assert_eq!(
+        backoff.to_string(),
+        "exponential(500ms x2 ≤ 30s), max 10 attempts"
+    );
+    assert_eq!(format!("{:?}", backoff), backoff.to_string());
+
+    let delay = Delay::builder()
+        .timeout(Duration::from_secs(60))
+        .exponential_backoff_capped(Duration::from_millis(100), 1.5, Duration::from_secs(5))
+        .build();
+    assert_eq!(
+        delay.to_string(),
+        "timeout(60s) + exponential(100ms x1.5 ≤ 5s)"
+    );
+    let delay = JitterWaiter::new(
+        Delay::fibonacci_backoff(Duration::from_secs(1), Duration::from_secs(60)),
+        Jitter::Full,
+    )
+    .then(Delay::exponential_backoff_ratio(
+        Duration::from_secs(1),
+        3,
+        2,
+        Duration::from_secs(u64::MAX),
+    ));
+    assert_eq!(
+        delay.to_string(),
+        "fibonacci(1s ≤ 60s) with full jitter then exponential(1s x3/2)"
+    );
+}
+
+#[test]
+fn waiters_resume_from_a_snapshot() {
+    use crate::{MockClock, WaiterError, WaiterExt, WaiterState};
+
+    let build = |clock: &MockClock| {
+        Delay::builder()
+            .exponential_backoff(Duration::from_secs(1), 2.0)
+            .timeout(Duration::from_secs(10))
+            .build()
+            .with_clock(clock.shared())
+    };
+    let clock = MockClock::new();
+    let mut waiter = build(&clock);
+    waiter.start();
+    for _ in 0..3 {
+        assert!(waiter.wait().is_ok());
+    }
+    let state = waiter.snapshot();
+    assert_eq!(
+        state,
+        WaiterState {
+            attempts: 3,
+            elapsed: Duration::from_secs(7),
+        }
+    );
+
+    // After a restart, the backoff continues where it was, without sleeping to catch up.
+    let clock = MockClock::new();
+    let mut waiter = build(&clock);
+    waiter.resume(state).unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(0));
+    assert_eq!(waiter.next_delay(), Some(Duration::from_secs(8)));
+    assert_eq!(
+        waiter.wait(),
+        Err(WaiterError::Timeout {
+            elapsed: Duration::from_secs(15),
+            attempts: 4,
+        })
+    );
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn retry_attribute_retries_the_function() {
+    use std::cell::Cell;
+
+    #[crate::macros::retry(policy = "throttle:1ms", max_attempts = 3)]
+    fn flaky(calls: &Cell<u32>, succeed_at: u32) -> Result<u32, String> {
+        calls.set(calls.get() + 1);
+        if calls.get() < succeed_at {
+            return Err(format!("attempt {} failed", calls.get()));
+        }
+        Ok(calls.get())
+    }
+
+    let calls = Cell::new(0);
+    assert_eq!(flaky(&calls, 3), Ok(3));
+    // The first attempt and 3 retries.
+    calls.set(0);
+    assert_eq!(flaky(&calls, 10), Err("attempt 4 failed".to_string()));
+}
+
+#[cfg(all(feature = "macros", feature = "async"))]
+#[tokio::test]
+async fn retry_attribute_awaits_async_functions() {
+    use std::cell::Cell;
+
+    #[crate::macros::retry(policy = "throttle:1ms", max_attempts = 3)]
+    async fn flaky(calls: &Cell<u32>, succeed_at: u32) -> Result<u32, String> {
+        calls.set(calls.get() + 1);
+        if calls.get() < succeed_at {
+            return Err(format!("attempt {} failed", calls.get()));
+        }
+        Ok(calls.get())
+    }
+
+    let calls = Cell::new(0);
+    let start = Instant::now();
+    assert_eq!(flaky(&calls, 3).await, Ok(3));
+    // Two waits of the throttle between the three attempts.
+    assert!(Instant::now().duration_since(start) >= Duration::from_millis(2));
+    calls.set(0);
+    assert_eq!(flaky(&calls, 10).await, Err("attempt 4 failed".to_string()));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn wait_spans_describe_the_policy_and_attempt() {
+    use crate::{MockClock, WaiterExt};
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    #[derive(Default)]
+    struct Fields(Vec<(String, String)>);
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        spans: Mutex<Vec<Fields>>,
+    }
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields::default();
+            span.record(&mut fields);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(fields);
+            Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let recorder = std::sync::Arc::new(Recorder::default());
+    let clock = MockClock::new();
+    let mut waiter = crate::ThrottleWaiter::new(Duration::from_millis(5)).with_stats();
+    waiter.set_clock(clock.shared());
+    waiter.start();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        waiter.wait().unwrap();
+        waiter.wait().unwrap();
+    });
+
+    let field = |span: &Fields, name: &str| {
+        span.0
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    };
+    let spans = recorder.spans.lock().unwrap();
+    assert_eq!(spans.len(), 2);
+    for (i, span) in spans.iter().enumerate() {
+        // The wrapper reports the policy it wraps, not its own name.
+        assert_eq!(field(span, "policy").as_deref(), Some("throttle(5ms)"));
+        assert_eq!(field(span, "attempt"), Some((i + 1).to_string()));
+        assert_eq!(field(span, "duration_ms").as_deref(), Some("5"));
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metered_waiter_records_waits_and_timeouts() {
+    use crate::{MockClock, WaiterExt};
+    use metrics::{GaugeValue, Key, Recorder, Unit};
+    use std::sync::Mutex;
+
+    /// The metric name, `policy` label and value of each recording.
+    #[derive(Default)]
+    struct TestRecorder {
+        records: Mutex<Vec<(String, String, f64)>>,
+    }
+    impl TestRecorder {
+        fn push(&self, key: Key, value: f64) {
+            let policy = key
+                .labels()
+                .find(|label| label.key() == "policy")
+                .map(|label| label.value().to_string())
+                .unwrap_or_default();
+            let name = key.name().to_string();
+            self.records.lock().unwrap().push((name, policy, value));
+        }
+    }
+    impl Recorder for TestRecorder {
+        fn register_counter(&self, _: Key, _: Option<Unit>, _: Option<&'static str>) {}
+        fn register_gauge(&self, _: Key, _: Option<Unit>, _: Option<&'static str>) {}
+        fn register_histogram(&self, _: Key, _: Option<Unit>, _: Option<&'static str>) {}
+        fn increment_counter(&self, key: Key, value: u64) {
+            self.push(key, value as f64);
+        }
+        fn update_gauge(&self, _: Key, _: GaugeValue) {}
+        fn record_histogram(&self, key: Key, value: f64) {
+            self.push(key, value);
+        }
+    }
+
+    let recorder: &'static TestRecorder = Box::leak(Box::new(TestRecorder::default()));
+    metrics::set_recorder(recorder).unwrap();
+
+    let clock = MockClock::new();
+    let mut waiter = Delay::throttle(Duration::from_secs(1))
+        .max_elapsed(Duration::from_millis(2500))
+        .metered("metered_test");
+    waiter.set_clock(clock.shared());
+    waiter.start();
+    for _ in 0..3 {
+        waiter.wait().unwrap();
+    }
+    assert!(waiter.wait().is_err());
+
+    let records = recorder.records.lock().unwrap();
+    let of = |name: &str| {
+        records
+            .iter()
+            .filter(|(n, policy, _)| n == name && policy == "metered_test")
+            .map(|(_, _, value)| *value)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(of("delay_waits_total"), vec![1.0; 3]);
+    assert_eq!(of("delay_wait_seconds"), vec![1.0, 1.0, 0.5]);
+    assert_eq!(of("delay_timeouts_total"), vec![1.0]);
+}
@@ -1,184 +1,535 @@
-#![cfg(not(feature = "no_std"))]
-use crate::{Waiter, WaiterError};
-use std::time::Duration;
+use crate::{Waiter, WaiterError, WaiterState};
+use core::time::Duration;
 
-use core::sync::atomic::{AtomicU64, Ordering};
-#[cfg(feature = "async")]
-use std::{future::Future, pin::Pin};
+#[cfg(not(feature = "no_std"))]
+use crate::clock::{deadline_after, sleep, Clock, SystemClock};
+#[cfg(not(feature = "no_std"))]
+use std::{sync::Arc, time::Instant};
 
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+use crate::clock::WaitFuture;
+#[cfg(all(feature = "async", feature = "no_std"))]
+use alloc::boxed::Box;
 #[cfg(feature = "async")]
-mod future {
-    use crate::WaiterError;
-    use std::future::Future;
-    use std::pin::Pin;
-    use std::sync::{Arc, Mutex};
-    use std::task::{Context, Poll, Waker};
-    use std::time::Duration;
-
-    /// A Future that resolves when a time has passed.
-    /// This is based on [https://rust-lang.github.io/async-book/02_execution/03_wakeups.html].
-    pub(super) struct ThrottleTimerFuture {
-        shared_state: Arc<Mutex<SharedState>>,
-    }
-
-    /// Shared state between the future and the waiting thread
-    struct SharedState {
-        /// Whether or not the sleep time has elapsed
-        completed: bool,
-
-        /// The waker for the task that `TimerFuture` is running on.
-        /// The thread can use this after setting `completed = true` to tell
-        /// `TimerFuture`'s task to wake up, see that `completed = true`, and
-        /// move forward.
-        waker: Option<Waker>,
-    }
-
-    impl Future for ThrottleTimerFuture {
-        type Output = Result<(), WaiterError>;
-        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            // Look at the shared state to see if the timer has already completed.
-            let mut shared_state = self.shared_state.lock().unwrap();
-            if shared_state.completed {
-                Poll::Ready(Ok(()))
-            } else {
-                // Set waker so that the thread can wake up the current task
-                // when the timer has completed, ensuring that the future is polled
-                // again and sees that `completed = true`.
-                //
-                // It's tempting to do this once rather than repeatedly cloning
-                // the waker each time. However, the `TimerFuture` can move between
-                // tasks on the executor, which could cause a stale waker pointing
-                // to the wrong task, preventing `TimerFuture` from waking up
-                // correctly.
-                //
-                // N.B. it's possible to check for this using the `Waker::will_wake`
-                // function, but we omit that here to keep things simple.
-                shared_state.waker = Some(cx.waker().clone());
-                Poll::Pending
-            }
-        }
-    }
-
-    impl ThrottleTimerFuture {
-        /// Create a new `TimerFuture` which will complete after the provided
-        /// timeout.
-        pub fn new(duration: Duration) -> Self {
-            let shared_state = Arc::new(Mutex::new(SharedState {
-                completed: false,
-                waker: None,
-            }));
-
-            // Spawn the new thread
-            let thread_shared_state = shared_state.clone();
-            std::thread::spawn(move || {
-                std::thread::sleep(duration);
-                let mut shared_state = thread_shared_state.lock().unwrap();
-                // Signal that the timer has completed and wake up the last
-                // task on which the future was polled, if one exists.
-                shared_state.completed = true;
-                if let Some(waker) = shared_state.waker.take() {
-                    waker.wake()
-                }
-            });
-
-            ThrottleTimerFuture { shared_state }
-        }
-    }
-}
+use core::{future::Future, pin::Pin};
 
+/// With `no_std` there is no clock to sleep on, so [Waiter::wait] sleeps with the function
+/// set with `set_sleep`, and only advances the waiter without one. Use
+/// [crate::WaiterExt::wait_with] to sleep with a given implementation instead, and
+/// `set_async_sleep` or the `embassy` feature for async waits.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ThrottleWaiter {
     throttle: Duration,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    fixed_cadence: bool,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    burst: u32,
+    /// The waits that can still return right away, as of the last deadline.
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    credits: f64,
+    /// When the last wait ends, for [ThrottleWaiter::with_fixed_cadence] and
+    /// [ThrottleWaiter::with_burst].
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_deadline: Option<Instant>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
 }
 impl ThrottleWaiter {
     pub fn new(throttle: Duration) -> Self {
-        Self { throttle }
+        Self {
+            throttle,
+            #[cfg(not(feature = "no_std"))]
+            fixed_cadence: false,
+            #[cfg(not(feature = "no_std"))]
+            burst: 0,
+            #[cfg(not(feature = "no_std"))]
+            credits: 0.0,
+            #[cfg(not(feature = "no_std"))]
+            last_deadline: None,
+            attempts: 0,
+            #[cfg(not(feature = "no_std"))]
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// A throttle allowing `ops_per_second` waits per second. A rate that is not positive
+    /// waits for as long as a duration can represent.
+    pub fn from_rate(ops_per_second: f64) -> Self {
+        Self::new(rate_period(1.0, ops_per_second))
+    }
+
+    /// A throttle allowing `ops` waits per minute, see [ThrottleWaiter::from_rate].
+    pub fn per_minute(ops: f64) -> Self {
+        Self::new(rate_period(60.0, ops))
+    }
+
+    /// A throttle allowing `ops` waits per hour, see [ThrottleWaiter::from_rate].
+    pub fn per_hour(ops: f64) -> Self {
+        Self::new(rate_period(3600.0, ops))
+    }
+
+    /// Keep a fixed cadence instead of always waiting for the whole throttle: the time
+    /// spent between the end of a wait and the next one is taken off the next delay, so a
+    /// loop doing work between waits still runs at the rate of the throttle. Work that took
+    /// longer than the throttle makes the next wait return right away.
+    #[cfg(not(feature = "no_std"))]
+    pub fn with_fixed_cadence(mut self) -> Self {
+        self.fixed_cadence = true;
+        self
+    }
+
+    /// Let up to `burst` waits return right away after a quiet period, then wait for the
+    /// throttle between the next ones. Each throttle of quiet time allows one more wait
+    /// without waiting, up to `burst`, so the average rate stays the one of the throttle.
+    /// The burst is available right after starting.
+    #[cfg(not(feature = "no_std"))]
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self.credits = f64::from(burst);
+        self
+    }
+
+    /// The waits that can return right away now.
+    #[cfg(not(feature = "no_std"))]
+    fn credits(&self) -> f64 {
+        let quiet = match self.last_deadline {
+            Some(last) if self.throttle.as_nanos() > 0 => {
+                self.clock
+                    .now()
+                    .saturating_duration_since(last)
+                    .as_secs_f64()
+                    / self.throttle.as_secs_f64()
+            }
+            _ => 0.0,
+        };
+        (self.credits + quiet).min(f64::from(self.burst))
+    }
+
+    fn delay(&self) -> Duration {
+        #[cfg(not(feature = "no_std"))]
+        if self.burst > 0 && self.credits() >= 1.0 {
+            return Duration::from_secs(0);
+        }
+        #[cfg(not(feature = "no_std"))]
+        if let (true, Some(last)) = (self.fixed_cadence, self.last_deadline) {
+            let since = self.clock.now().saturating_duration_since(last);
+            return self.throttle.checked_sub(since).unwrap_or_default();
+        }
+        self.throttle
+    }
+
+    /// Like [Waiter::async_wait], as a future that can be named and doesn't need to be
+    /// boxed. With the system clock and no async runtime feature, waits don't allocate.
+    #[cfg(all(feature = "async", not(feature = "no_std")))]
+    pub fn wait_future(&mut self) -> ThrottleWait {
+        WaitFuture::new(self.advance(), self.clock.as_ref(), self)
     }
 }
+
+/// The future of [ThrottleWaiter::wait_future].
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub type ThrottleWait = WaitFuture;
+
 impl Waiter for ThrottleWaiter {
+    #[cfg(feature = "no_std")]
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        crate::sleep::no_std_block(self.advance())
+    }
+
+    #[cfg(not(feature = "no_std"))]
     fn wait(&mut self) -> Result<(), WaiterError> {
-        std::thread::sleep(self.throttle);
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+
+    fn start(&mut self) {
+        self.attempts = 0;
+        #[cfg(not(feature = "no_std"))]
+        {
+            self.credits = f64::from(self.burst);
+            self.last_deadline = None;
+        }
+    }
+
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let delay = self.delay();
+        self.attempts += 1;
+        #[cfg(not(feature = "no_std"))]
+        {
+            if self.burst > 0 {
+                let credits = self.credits();
+                self.credits = if credits >= 1.0 {
+                    credits - 1.0
+                } else {
+                    credits
+                };
+            }
+            self.last_deadline = Some(deadline_after(self.clock.now(), delay));
+        }
+        Ok(delay)
+    }
+
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.delay())
+    }
+
+    /// The burst and cadence start over, only the attempts are restored.
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.start();
+        self.attempts = state.attempts;
+        Ok(())
+    }
 
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "throttle({:?})", self.throttle)?;
+        #[cfg(not(feature = "no_std"))]
+        if self.fixed_cadence {
+            f.write_str(" at a fixed cadence")?;
+        }
+        #[cfg(not(feature = "no_std"))]
+        if self.burst > 0 {
+            write!(f, ", burst {}", self.burst)?;
+        }
         Ok(())
     }
 
-    #[cfg(feature = "async")]
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    #[cfg(all(feature = "async", feature = "no_std"))]
     fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
-        Box::pin(future::ThrottleTimerFuture::new(self.throttle))
+        crate::sleep::no_std_sleep(self.advance())
     }
+
+    #[cfg(all(feature = "async", not(feature = "no_std")))]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        Box::pin(self.wait_future())
+    }
+}
+
+describe_waiter!(ThrottleWaiter);
+
+/// The future of [ExponentialBackoffWaiter::wait_future].
+#[cfg(all(feature = "async", not(feature = "no_std")))]
+pub type BackoffWait = WaitFuture;
+
+/// What an [ExponentialBackoffWaiter] does when its next delay would be longer than its cap.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CapBehavior {
+    /// Keep waiting for the cap.
+    Saturate,
+    /// Go back to the initial delay.
+    Reset,
+    /// Give up with [WaiterError::Timeout] instead of waiting longer than the cap.
+    Timeout,
 }
 
+/// The multiplier is applied as a fixed point ratio with this denominator, so the delays
+/// don't depend on float rounding.
+const MULTIPLIER_DENOMINATOR: u64 = 1 << 20;
+
+/// See [ThrottleWaiter] for waiting with `no_std`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExponentialBackoffWaiter {
-    next_as_micros: Option<AtomicU64>,
-    initial_as_micros: u64,
-    multiplier: f64,
-    cap_as_micros: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    next_as_nanos: Option<u64>,
+    initial_as_nanos: u64,
+    numerator: u64,
+    denominator: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    increment_as_nanos: u64,
+    cap_as_nanos: u64,
+    cap_behavior: CapBehavior,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: usize,
+    #[cfg(not(feature = "no_std"))]
+    decay: Option<Duration>,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_deadline: Option<Instant>,
+    #[cfg(not(feature = "no_std"))]
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
 }
 impl ExponentialBackoffWaiter {
     pub fn new(initial: Duration, multiplier: f32, cap: Duration) -> Self {
+        // Rounded to the nearest. Float to int casts saturate, and map NaN to 0.
+        let numerator = (f64::from(multiplier) * MULTIPLIER_DENOMINATOR as f64 + 0.5) as u64;
         ExponentialBackoffWaiter {
-            next_as_micros: None,
-            initial_as_micros: initial.as_micros() as u64,
-            multiplier: multiplier as f64,
-            cap_as_micros: cap.as_micros() as u64,
+            next_as_nanos: None,
+            initial_as_nanos: saturating_nanos(initial),
+            numerator,
+            denominator: MULTIPLIER_DENOMINATOR,
+            increment_as_nanos: 0,
+            cap_as_nanos: saturating_nanos(cap),
+            cap_behavior: CapBehavior::Saturate,
+            attempts: 0,
+            #[cfg(not(feature = "no_std"))]
+            decay: None,
+            #[cfg(not(feature = "no_std"))]
+            last_deadline: None,
+            #[cfg(not(feature = "no_std"))]
+            clock: SystemClock::shared(),
         }
     }
 
-    fn increment(&mut self) -> Result<Duration, WaiterError> {
-        let next = self
-            .next_as_micros
-            .as_ref()
-            .ok_or(WaiterError::NotStarted)?;
-        let current = next.load(Ordering::Relaxed);
-
-        // Find the next throttle.
-        let next = u64::min(
-            (current as f64 * self.multiplier) as u64,
-            self.cap_as_micros,
+    /// Multiply the delay by `numerator / denominator` at each wait. This only uses integer
+    /// arithmetic, so the delays are the same on every platform.
+    ///
+    /// Panics if the denominator is zero.
+    pub fn with_ratio(initial: Duration, numerator: u64, denominator: u64, cap: Duration) -> Self {
+        assert!(
+            denominator != 0,
+            "the denominator of the multiplier is zero"
         );
-        self.next_as_micros
-            .as_mut()
-            .unwrap()
-            .store(next, Ordering::Relaxed);
-        Ok(Duration::from_micros(current))
-    }
-}
-impl Clone for ExponentialBackoffWaiter {
-    fn clone(&self) -> Self {
         Self {
-            next_as_micros: self
-                .next_as_micros
-                .as_ref()
-                .map(|a| AtomicU64::new(a.load(Ordering::Relaxed))),
-            ..*self
+            numerator,
+            denominator,
+            ..Self::new(initial, 1.0, cap)
+        }
+    }
+
+    /// Add a fixed increment after multiplying, so each delay is `previous * multiplier +
+    /// increment`. There is no increment by default.
+    pub fn with_increment(mut self, increment: Duration) -> Self {
+        self.increment_as_nanos = saturating_nanos(increment);
+        self
+    }
+
+    /// Set what to do when the delay reaches the cap. The default is
+    /// [CapBehavior::Saturate].
+    pub fn with_cap_behavior(mut self, behavior: CapBehavior) -> Self {
+        self.cap_behavior = behavior;
+        self
+    }
+
+    /// Shrink the delay by one step for every quiet period without waits, so a long-lived
+    /// waiter goes back toward the initial delay once things calm down. The period starts
+    /// at the end of the last wait. It never shrinks by default.
+    #[cfg(not(feature = "no_std"))]
+    pub fn with_decay(mut self, quiet: Duration) -> Self {
+        self.decay = Some(quiet).filter(|quiet| *quiet > Duration::from_secs(0));
+        self
+    }
+
+    /// Shrink the delay by one step, e.g. after a successful attempt, without going below
+    /// the initial delay.
+    pub fn report_success(&mut self) {
+        self.shrink(1);
+    }
+
+    fn shrink(&mut self, mut steps: u128) {
+        let initial = u128::from(self.initial_as_nanos);
+        let mut current = match self.next_as_nanos {
+            Some(current) => u128::from(current),
+            None => return,
+        };
+        let increment = u128::from(self.increment_as_nanos);
+        // A multiplier of at most one never grew the delay, so there is nothing to undo.
+        if self.numerator <= self.denominator && increment == 0 {
+            current = initial;
+        }
+        while steps > 0 && current > initial {
+            let shrunk = match current.checked_sub(increment) {
+                Some(rest) if self.numerator > 0 => {
+                    rest * u128::from(self.denominator) / u128::from(self.numerator)
+                }
+                _ => initial,
+            };
+            // Undoing a shrinking multiplier would grow the delay, go back to the start instead.
+            current = if shrunk < current { shrunk } else { initial };
+            steps -= 1;
         }
+        self.next_as_nanos = Some(current.max(initial) as u64);
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    fn decay(&mut self) {
+        if let (Some(quiet), Some(last)) = (self.decay, self.last_deadline) {
+            let now = self.clock.now();
+            let steps = now.saturating_duration_since(last).as_nanos() / quiet.as_nanos();
+            self.shrink(steps);
+        }
+    }
+
+    /// Like [Waiter::async_wait], as a future that can be named and doesn't need to be
+    /// boxed, see [ThrottleWaiter::wait_future].
+    #[cfg(all(feature = "async", not(feature = "no_std")))]
+    pub fn wait_future(&mut self) -> BackoffWait {
+        WaitFuture::new(self.increment(), self.clock.as_ref(), self)
+    }
+
+    fn increment(&mut self) -> Result<Duration, WaiterError> {
+        #[cfg(not(feature = "no_std"))]
+        self.decay();
+        let current = self.next_as_nanos.ok_or(WaiterError::NotStarted)?;
+        if current > self.cap_as_nanos && self.cap_behavior == CapBehavior::Timeout {
+            return Err(WaiterError::Timeout {
+                elapsed: Duration::from_secs(0),
+                attempts: self.attempts,
+            });
+        }
+
+        // A u64 times a u64 plus a u64 always fits in a u128, so this can't overflow.
+        let grown = u128::from(current) * u128::from(self.numerator) / u128::from(self.denominator)
+            + u128::from(self.increment_as_nanos);
+        let next = if grown <= u128::from(self.cap_as_nanos) {
+            grown as u64
+        } else {
+            match self.cap_behavior {
+                CapBehavior::Saturate => self.cap_as_nanos,
+                CapBehavior::Reset => self.initial_as_nanos,
+                CapBehavior::Timeout => u64::MAX,
+            }
+        };
+        self.next_as_nanos = Some(next);
+        self.attempts += 1;
+        #[cfg(not(feature = "no_std"))]
+        {
+            self.last_deadline = Some(deadline_after(
+                self.clock.now(),
+                Duration::from_nanos(current),
+            ));
+        }
+        Ok(Duration::from_nanos(current))
     }
 }
+
+/// Write a multiplier given as a ratio. Float multipliers are written as decimals, rounded
+/// to three places.
+fn describe_ratio(
+    f: &mut core::fmt::Formatter<'_>,
+    numerator: u64,
+    denominator: u64,
+) -> core::fmt::Result {
+    if denominator != MULTIPLIER_DENOMINATOR {
+        return write!(f, "{}/{}", numerator, denominator);
+    }
+    let thousandths =
+        (u128::from(numerator) * 1000 + u128::from(denominator) / 2) / u128::from(denominator);
+    write!(f, "{}", thousandths / 1000)?;
+    let (mut fraction, mut width) = (thousandths % 1000, 3);
+    if fraction == 0 {
+        return Ok(());
+    }
+    while fraction % 10 == 0 {
+        fraction /= 10;
+        width -= 1;
+    }
+    write!(f, ".{:0width$}", fraction, width = width)
+}
+
+/// The time between operations at a rate of `ops` per `seconds`, saturating instead of
+/// panicking on rates that are not positive or too small.
+fn rate_period(seconds: f64, ops: f64) -> Duration {
+    let period = seconds / ops;
+    if period >= 0.0 && period < u64::MAX as f64 {
+        Duration::from_secs_f64(period)
+    } else {
+        Duration::from_secs(u64::MAX)
+    }
+}
+
+/// The nanoseconds of a duration, saturating at about 584 years.
+fn saturating_nanos(duration: Duration) -> u64 {
+    core::cmp::min(duration.as_nanos(), u128::from(u64::MAX)) as u64
+}
+
 impl Waiter for ExponentialBackoffWaiter {
     fn restart(&mut self) -> Result<(), WaiterError> {
-        if self.next_as_micros.is_none() {
+        if self.next_as_nanos.is_none() {
             Err(WaiterError::NotStarted)
         } else {
-            self.next_as_micros = Some(AtomicU64::new(self.initial_as_micros));
+            self.start();
             Ok(())
         }
     }
 
     fn start(&mut self) {
-        self.next_as_micros = Some(AtomicU64::new(self.initial_as_micros));
+        self.next_as_nanos = Some(self.initial_as_nanos);
+        self.attempts = 0;
+        #[cfg(not(feature = "no_std"))]
+        {
+            self.last_deadline = None;
+        }
     }
 
+    #[cfg(feature = "no_std")]
     fn wait(&mut self) -> Result<(), WaiterError> {
-        let current = self.increment()?;
-        std::thread::sleep(current);
-        Ok(())
+        crate::sleep::no_std_block(self.advance())
     }
 
-    #[cfg(feature = "async")]
-    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
-        match self.increment() {
-            Ok(current) => Box::pin(future::ThrottleTimerFuture::new(current)),
-            Err(e) => Box::pin(futures_util::future::err(e)),
+    #[cfg(not(feature = "no_std"))]
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.increment(), self.clock.as_ref(), self)
+    }
+
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.increment()
+    }
+
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        self.next_as_nanos
+            .filter(|&next| next <= self.cap_as_nanos || self.cap_behavior != CapBehavior::Timeout)
+            .map(Duration::from_nanos)
+    }
+
+    fn describe(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "exponential({:?} x",
+            Duration::from_nanos(self.initial_as_nanos)
+        )?;
+        describe_ratio(f, self.numerator, self.denominator)?;
+        if self.increment_as_nanos > 0 {
+            write!(f, " +{:?}", Duration::from_nanos(self.increment_as_nanos))?;
+        }
+        crate::describe_cap(f, Duration::from_nanos(self.cap_as_nanos))?;
+        match self.cap_behavior {
+            CapBehavior::Saturate => {}
+            CapBehavior::Reset => f.write_str(", reset at cap")?,
+            CapBehavior::Timeout => f.write_str(", give up at cap")?,
         }
+        #[cfg(not(feature = "no_std"))]
+        if let Some(quiet) = self.decay {
+            write!(f, ", decay every {:?}", quiet)?;
+        }
+        f.write_str(")")
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    #[cfg(all(feature = "async", feature = "no_std"))]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        crate::sleep::no_std_sleep(self.increment())
+    }
+
+    #[cfg(all(feature = "async", not(feature = "no_std")))]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        Box::pin(self.wait_future())
     }
 }
+
+describe_waiter!(ExponentialBackoffWaiter);
@@ -1,35 +1,333 @@
 #![cfg(not(feature = "no_std"))]
-use crate::{Waiter, WaiterError};
-use std::time::{Duration, Instant};
+use crate::clock::{sleep, Clock, Stamp, SystemClock, TimeSource};
+use crate::{Waiter, WaiterError, WaiterState};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "async")]
+use crate::clock::async_sleep;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeoutWaiter {
     timeout: Duration,
-    start: Option<Instant>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    source: TimeSource,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    start: Option<Stamp>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "SystemClock::shared"))]
+    clock: Arc<dyn Clock>,
 }
 impl TimeoutWaiter {
     pub fn new(timeout: Duration) -> Self {
         Self {
             timeout,
+            source: TimeSource::Monotonic,
             start: None,
+            clock: SystemClock::shared(),
         }
     }
+
+    /// Measure the timeout with this time source, [TimeSource::Monotonic] by default.
+    pub fn with_time_source(mut self, source: TimeSource) -> Self {
+        self.source = source;
+        self
+    }
 }
 impl Waiter for TimeoutWaiter {
     fn restart(&mut self) -> Result<(), WaiterError> {
         let _ = self.start.ok_or(WaiterError::NotStarted)?;
-        self.start = Some(Instant::now());
+        self.start = Some(Stamp::now(self.clock.as_ref()));
         Ok(())
     }
     fn start(&mut self) {
-        self.start = Some(Instant::now());
+        self.start = Some(Stamp::now(self.clock.as_ref()));
     }
     fn wait(&mut self) -> Result<(), WaiterError> {
-        let start = self.start.ok_or(WaiterError::NotStarted)?;
-        if start.elapsed() > self.timeout {
-            Err(WaiterError::Timeout)
+        let _ = self.start.ok_or(WaiterError::NotStarted)?;
+        if self.elapsed() > self.timeout {
+            Err(WaiterError::Timeout {
+                elapsed: self.elapsed(),
+                attempts: self.attempts(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn elapsed(&self) -> Duration {
+        self.start.map_or(Duration::from_secs(0), |start| {
+            start.elapsed(self.clock.as_ref(), self.source)
+        })
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(Duration::from_secs(0))
+    }
+    fn remaining(&self) -> Option<Duration> {
+        Some(
+            self.timeout
+                .checked_sub(self.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        )
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.start = Some(Stamp::before(self.clock.as_ref(), state.elapsed));
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            TimeSource::Monotonic => write!(f, "timeout({:?})", self.timeout),
+            TimeSource::WallClock => write!(f, "timeout({:?} wall clock)", self.timeout),
+        }
+    }
+}
+
+describe_waiter!(TimeoutWaiter);
+
+#[derive(Copy, Clone)]
+enum Deadline {
+    Monotonic(Instant),
+    WallClock(SystemTime),
+}
+
+/// A waiter that doesn't wait, but times out once an absolute deadline has passed. Unlike
+/// [TimeoutWaiter], the deadline is the same for every clone and is not moved by restart.
+#[derive(Clone)]
+pub struct DeadlineWaiter {
+    deadline: Deadline,
+    start: Option<Stamp>,
+    clock: Arc<dyn Clock>,
+}
+impl DeadlineWaiter {
+    pub fn new(deadline: Instant) -> Self {
+        Self {
+            deadline: Deadline::Monotonic(deadline),
+            start: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// A deadline on the wall clock, see [TimeSource::WallClock].
+    pub fn at_system_time(deadline: SystemTime) -> Self {
+        Self {
+            deadline: Deadline::WallClock(deadline),
+            start: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    fn source(&self) -> TimeSource {
+        match self.deadline {
+            Deadline::Monotonic(_) => TimeSource::Monotonic,
+            Deadline::WallClock(_) => TimeSource::WallClock,
+        }
+    }
+
+    fn passed(&self) -> bool {
+        match self.deadline {
+            Deadline::Monotonic(deadline) => self.clock.now() > deadline,
+            Deadline::WallClock(deadline) => self.clock.system_time() > deadline,
+        }
+    }
+}
+impl Waiter for DeadlineWaiter {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.start.ok_or(WaiterError::NotStarted)?;
+        self.start = Some(Stamp::now(self.clock.as_ref()));
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.start = Some(Stamp::now(self.clock.as_ref()));
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        if self.start.is_none() {
+            Err(WaiterError::NotStarted)
+        } else if self.passed() {
+            Err(WaiterError::Timeout {
+                elapsed: self.elapsed(),
+                attempts: self.attempts(),
+            })
         } else {
             Ok(())
         }
     }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn elapsed(&self) -> Duration {
+        self.start.map_or(Duration::from_secs(0), |start| {
+            start.elapsed(self.clock.as_ref(), self.source())
+        })
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(Duration::from_secs(0))
+    }
+    fn remaining(&self) -> Option<Duration> {
+        Some(match self.deadline {
+            Deadline::Monotonic(deadline) => deadline.saturating_duration_since(self.clock.now()),
+            Deadline::WallClock(deadline) => deadline
+                .duration_since(self.clock.system_time())
+                .unwrap_or_else(|_| Duration::from_secs(0)),
+        })
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.start = Some(Stamp::before(self.clock.as_ref(), state.elapsed));
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.deadline {
+            Deadline::Monotonic(deadline) => write!(
+                f,
+                "deadline(in {:?})",
+                deadline.saturating_duration_since(self.clock.now())
+            ),
+            Deadline::WallClock(deadline) => write!(
+                f,
+                "deadline(in {:?} wall clock)",
+                deadline
+                    .duration_since(self.clock.system_time())
+                    .unwrap_or_else(|_| Duration::from_secs(0))
+            ),
+        }
+    }
+}
+
+describe_waiter!(DeadlineWaiter);
+
+/// Where a [MaxElapsedWaiter] is in its budget, given to its progress callback before each
+/// wait, e.g. to show "retrying in 8s, giving up in 42s".
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Progress {
+    pub elapsed: Duration,
+    pub remaining: Duration,
+    pub next_delay: Duration,
+}
+
+pub(crate) type OnProgress = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// A waiter that gives up with [WaiterError::Timeout] once it has been started for longer
+/// than a budget, whatever its inner waiter does. The budget is checked before each wait, and
+/// a delay longer than what is left of it is cut short, so the last wait ends with the budget.
+#[derive(Clone)]
+pub struct MaxElapsedWaiter<W: Waiter> {
+    inner: W,
+    budget: Duration,
+    source: TimeSource,
+    start: Option<Stamp>,
+    on_progress: Option<OnProgress>,
+    clock: Arc<dyn Clock>,
+}
+impl<W: Waiter> MaxElapsedWaiter<W> {
+    pub fn new(inner: W, budget: Duration) -> Self {
+        Self {
+            inner,
+            budget,
+            source: TimeSource::Monotonic,
+            start: None,
+            on_progress: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Called before each wait with the time elapsed and left in the budget, and the delay
+    /// about to be waited.
+    pub fn on_progress<F: Fn(&Progress) + Send + Sync + 'static>(self, f: F) -> Self {
+        self.with_progress(Arc::new(f))
+    }
+
+    pub(crate) fn with_progress(mut self, f: OnProgress) -> Self {
+        self.on_progress = Some(f);
+        self
+    }
+
+    /// Measure the budget with this time source, [TimeSource::Monotonic] by default.
+    pub fn with_time_source(mut self, source: TimeSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// The time elapsed, or a timeout once it exceeds the budget.
+    fn check(&self) -> Result<Duration, WaiterError> {
+        let start = self.start.ok_or(WaiterError::NotStarted)?;
+        let elapsed = start.elapsed(self.clock.as_ref(), self.source);
+        if elapsed >= self.budget {
+            Err(WaiterError::Timeout {
+                elapsed,
+                attempts: self.inner.attempts(),
+            })
+        } else {
+            Ok(elapsed)
+        }
+    }
 }
+impl<W: 'static + Waiter + Clone> Waiter for MaxElapsedWaiter<W> {
+    fn restart(&mut self) -> Result<(), WaiterError> {
+        let _ = self.start.ok_or(WaiterError::NotStarted)?;
+        self.inner.restart()?;
+        self.start = Some(Stamp::now(self.clock.as_ref()));
+        Ok(())
+    }
+    fn start(&mut self) {
+        self.inner.start();
+        self.start = Some(Stamp::now(self.clock.as_ref()));
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        sleep(self.advance(), self.clock.as_ref(), self)
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        let elapsed = self.check()?;
+        let remaining = self.budget - elapsed;
+        let delay = self.inner.advance()?.min(remaining);
+        if let Some(f) = &self.on_progress {
+            f(&Progress {
+                elapsed,
+                remaining,
+                next_delay: delay,
+            });
+        }
+        Ok(delay)
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock.clone());
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.inner.attempts()
+    }
+    fn elapsed(&self) -> Duration {
+        self.start.map_or(Duration::from_secs(0), |start| {
+            start.elapsed(self.clock.as_ref(), self.source)
+        })
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        let elapsed = self.check().ok()?;
+        Some(self.inner.next_delay()?.min(self.budget - elapsed))
+    }
+    fn remaining(&self) -> Option<Duration> {
+        let remaining = self
+            .budget
+            .checked_sub(self.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+        crate::min_remaining(Some(remaining), self.inner.remaining())
+    }
+    fn resume(&mut self, state: WaiterState) -> Result<(), WaiterError> {
+        self.inner.resume(state)?;
+        self.start = Some(Stamp::before(self.clock.as_ref(), state.elapsed));
+        Ok(())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.describe(f)?;
+        write!(f, ", max {:?} elapsed", self.budget)
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        async_sleep(self.advance(), self.clock.as_ref(), self)
+    }
+}
+
+describe_waiter!(MaxElapsedWaiter<W>);
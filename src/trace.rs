@@ -0,0 +1,36 @@
+#![cfg(all(feature = "tracing", not(feature = "no_std")))]
+use crate::{Waiter, WaiterError};
+use std::fmt;
+use std::time::Duration;
+
+/// Display a waiter with its [Waiter::describe], for the fields of the spans.
+struct Policy<'a>(&'a dyn Waiter);
+
+impl fmt::Display for Policy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.describe(f)
+    }
+}
+
+/// The span entered for the duration of a wait, with the description of the waiter and its
+/// attempts so far. A waiter that gives up doesn't wait, so an event is emitted instead and
+/// no span is returned.
+pub(crate) fn wait_span(
+    waiter: &dyn Waiter,
+    delay: &Result<Duration, WaiterError>,
+) -> tracing::Span {
+    let policy = Policy(waiter);
+    let attempt = waiter.attempts() as u64;
+    match delay {
+        Ok(delay) => tracing::debug_span!(
+            "delay.wait",
+            policy = %policy,
+            attempt,
+            duration_ms = delay.as_millis() as u64
+        ),
+        Err(error) => {
+            tracing::debug!(policy = %policy, attempt, ?error, "delay.gave_up");
+            tracing::Span::none()
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(not(feature = "no_std"))]
+use crate::clock::{Clock, SystemClock};
+use crate::{Waiter, WaiterError};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+/// A waiter that waits until a wall clock time, e.g. when a quota resets. The wait sleeps in
+/// steps and checks the wall clock again after each, so it stays correct when the clock is
+/// adjusted meanwhile. Once the time has passed, waits return right away.
+#[derive(Clone)]
+pub struct SystemTimeWaiter {
+    target: SystemTime,
+    recheck: Duration,
+    attempts: usize,
+    clock: Arc<dyn Clock>,
+}
+impl SystemTimeWaiter {
+    pub fn new(target: SystemTime) -> Self {
+        Self {
+            target,
+            recheck: Duration::from_secs(1),
+            attempts: 0,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// The longest step to sleep before checking the wall clock again, 1s by default.
+    pub fn with_recheck(mut self, recheck: Duration) -> Self {
+        self.recheck = recheck.max(Duration::from_millis(1));
+        self
+    }
+
+    fn remaining(&self) -> Duration {
+        self.target
+            .duration_since(self.clock.system_time())
+            .unwrap_or_else(|_| Duration::from_secs(0))
+    }
+}
+impl Waiter for SystemTimeWaiter {
+    fn start(&mut self) {
+        self.attempts = 0;
+    }
+    fn wait(&mut self) -> Result<(), WaiterError> {
+        self.attempts += 1;
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::wait_span(self, &Ok(self.remaining()));
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        loop {
+            let remaining = self.remaining();
+            if remaining == Duration::from_secs(0) {
+                return Ok(());
+            }
+            self.clock.sleep(remaining.min(self.recheck));
+        }
+    }
+    fn advance(&mut self) -> Result<Duration, WaiterError> {
+        self.attempts += 1;
+        Ok(self.remaining())
+    }
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+    fn attempts(&self) -> usize {
+        self.attempts
+    }
+    fn next_delay(&self) -> Option<Duration> {
+        Some(self.remaining())
+    }
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "until(in {:?})", self.remaining())
+    }
+
+    #[cfg(feature = "async")]
+    fn async_wait(&mut self) -> Pin<Box<dyn Future<Output = Result<(), WaiterError>> + Send>> {
+        self.attempts += 1;
+        let waiter = self.clone();
+        Box::pin(async move {
+            loop {
+                let remaining = waiter.remaining();
+                if remaining == Duration::from_secs(0) {
+                    return Ok(());
+                }
+                waiter
+                    .clock
+                    .async_sleep(remaining.min(waiter.recheck))
+                    .await;
+            }
+        })
+    }
+}
+
+describe_waiter!(SystemTimeWaiter);
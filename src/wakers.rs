@@ -0,0 +1,81 @@
+#![cfg(all(feature = "async", not(feature = "no_std")))]
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+#[derive(Default)]
+struct WakersInner {
+    wakers: HashMap<u64, Waker>,
+    next_id: u64,
+}
+
+/// The wakers of the futures waiting on a shared state, e.g. for a handle to be cancelled.
+/// Each future keeps its waker in a [WakerSlot], so polling it again replaces the waker
+/// instead of adding one, and dropping it removes the waker.
+///
+/// Register while holding the lock of the state the future checks, and wake after changing
+/// that state, so a wake up can't be missed. Clones share the same wakers.
+#[derive(Clone, Default)]
+pub(crate) struct Wakers {
+    inner: Arc<Mutex<WakersInner>>,
+}
+
+impl Wakers {
+    /// A slot for the waker of a new future, empty until it registers.
+    pub(crate) fn slot(&self) -> WakerSlot {
+        WakerSlot {
+            wakers: self.clone(),
+            id: None,
+        }
+    }
+
+    /// Wake all the futures registered, which register again if they are still pending.
+    pub(crate) fn wake_all(&self) {
+        let wakers: Vec<Waker> = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.wakers.drain().map(|(_, waker)| waker).collect()
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// The place of a future in [Wakers], removed when dropped.
+pub(crate) struct WakerSlot {
+    wakers: Wakers,
+    id: Option<u64>,
+}
+
+impl WakerSlot {
+    /// Wake this waker on the next [Wakers::wake_all], instead of the one registered before.
+    pub(crate) fn register(&mut self, waker: &Waker) {
+        let mut inner = self.wakers.inner.lock().unwrap();
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                inner.next_id += 1;
+                inner.next_id
+            }
+        };
+        inner.wakers.insert(id, waker.clone());
+        self.id = Some(id);
+    }
+
+    /// Whether the future registered since it was created or last removed.
+    pub(crate) fn is_registered(&self) -> bool {
+        self.id.is_some()
+    }
+
+    pub(crate) fn remove(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.wakers.inner.lock().unwrap().wakers.remove(&id);
+        }
+    }
+}
+
+impl Drop for WakerSlot {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}